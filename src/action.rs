@@ -0,0 +1,241 @@
+use std::{fmt, str::FromStr};
+
+/// Something the picker does in response to a key
+///
+/// Actions are named in kebab-case in config files, e.g. `toggle-preview`. What an action does
+/// depends on the mode the picker is in, e.g. `up` moves to the previous item when searching, the
+/// previous suggestion when editing and scrolls the help screen when it is shown.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// Move to the previous item
+    Up,
+    /// Move to the next item
+    Down,
+    /// Move up by a page
+    PageUp,
+    /// Move down by a page
+    PageDown,
+    /// Move to the first item
+    First,
+    /// Move to the last item
+    Last,
+    /// Toggle the selection of the current item
+    Toggle,
+    /// Toggle the selection of the current item and move to the next one
+    ToggleDown,
+    /// Return the selected items, or create the edited items when editing
+    Accept,
+    /// Exit without returning anything
+    Abort,
+    /// Clear the query or exit when searching, leave editing or help otherwise
+    Cancel,
+    /// Clear the query or the editing text
+    ClearQuery,
+    /// Move the cursor back by one character
+    BackwardChar,
+    /// Move the cursor forward by one character
+    ForwardChar,
+    /// Move the cursor back by one word
+    BackwardWord,
+    /// Move the cursor forward by one word
+    ForwardWord,
+    /// Move the cursor to the start of the text
+    BeginningOfLine,
+    /// Move the cursor to the end of the text
+    EndOfLine,
+    /// Delete the character before the cursor
+    BackwardDeleteChar,
+    /// Delete the character under the cursor
+    DeleteChar,
+    /// Delete the word before the cursor
+    BackwardKillWord,
+    /// Delete the word after the cursor
+    KillWord,
+    /// Delete everything after the cursor
+    KillLine,
+    /// Edit the text of the current item, if the picker is editable
+    EditItem,
+    /// Request a new item, if the picker is editable
+    NewItem,
+    /// Show the help screen, or close it when it is shown
+    Help,
+    /// Load the items from the picker's source again
+    Reload,
+    /// Switch between passing the query to the source and filtering the loaded items
+    ToggleInteractive,
+    /// Show or hide the preview pane
+    TogglePreview,
+    /// Move the preview pane to the next side of the screen
+    CyclePreview,
+    /// Scroll the preview up by one line
+    PreviewUp,
+    /// Scroll the preview down by one line
+    PreviewDown,
+    /// Scroll the preview up by a page
+    PreviewPageUp,
+    /// Scroll the preview down by a page
+    PreviewPageDown,
+    /// Run a command in the foreground, the picker is suspended until it exits
+    Execute(String),
+    /// Run a command in the background without showing its output
+    ExecuteSilent(String),
+    /// Exit and replace the picker with a command
+    Become(String),
+    /// Do nothing, used to remove a default binding
+    Ignore,
+    /// Insert a character into the query or the editing text
+    Insert(char),
+}
+
+/// Names of the actions in config files, in the order they are listed on the help screen
+const ACTION_NAMES: &[(&str, Action)] = &[
+    ("up", Action::Up),
+    ("down", Action::Down),
+    ("page-up", Action::PageUp),
+    ("page-down", Action::PageDown),
+    ("first", Action::First),
+    ("last", Action::Last),
+    ("toggle", Action::Toggle),
+    ("toggle-down", Action::ToggleDown),
+    ("accept", Action::Accept),
+    ("cancel", Action::Cancel),
+    ("abort", Action::Abort),
+    ("clear-query", Action::ClearQuery),
+    ("backward-char", Action::BackwardChar),
+    ("forward-char", Action::ForwardChar),
+    ("backward-word", Action::BackwardWord),
+    ("forward-word", Action::ForwardWord),
+    ("beginning-of-line", Action::BeginningOfLine),
+    ("end-of-line", Action::EndOfLine),
+    ("backward-delete-char", Action::BackwardDeleteChar),
+    ("delete-char", Action::DeleteChar),
+    ("backward-kill-word", Action::BackwardKillWord),
+    ("kill-word", Action::KillWord),
+    ("kill-line", Action::KillLine),
+    ("edit-item", Action::EditItem),
+    ("new-item", Action::NewItem),
+    ("help", Action::Help),
+    ("reload", Action::Reload),
+    ("toggle-interactive", Action::ToggleInteractive),
+    ("toggle-preview", Action::TogglePreview),
+    ("cycle-preview", Action::CyclePreview),
+    ("preview-up", Action::PreviewUp),
+    ("preview-down", Action::PreviewDown),
+    ("preview-page-up", Action::PreviewPageUp),
+    ("preview-page-down", Action::PreviewPageDown),
+    ("ignore", Action::Ignore),
+];
+
+impl Action {
+    /// Position of the action on the help screen, actions without a name are listed last
+    pub(crate) fn help_order(&self) -> usize {
+        ACTION_NAMES
+            .iter()
+            .position(|(_, action)| action == self)
+            .unwrap_or(ACTION_NAMES.len())
+    }
+}
+
+impl FromStr for Action {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        // actions that run a command take it in parentheses, e.g. `execute(vim {})`
+        if let Some((action, command)) = name
+            .trim()
+            .strip_suffix(')')
+            .and_then(|name| name.split_once('('))
+        {
+            if command.trim().is_empty() {
+                return Err(format!("missing command in action {name:?}"));
+            }
+            let command = command.to_string();
+            return match action.trim().to_lowercase().as_str() {
+                "execute" => Ok(Action::Execute(command)),
+                "execute-silent" => Ok(Action::ExecuteSilent(command)),
+                "become" => Ok(Action::Become(command)),
+                _ => Err(format!("unknown action {name:?}")),
+            };
+        }
+
+        ACTION_NAMES
+            .iter()
+            .find(|(action_name, _)| action_name.eq_ignore_ascii_case(name.trim()))
+            .map(|(_, action)| action.clone())
+            .ok_or_else(|| format!("unknown action {name:?}"))
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::Insert(c) => write!(f, "insert({c})"),
+            Action::Execute(command) => write!(f, "execute({command})"),
+            Action::ExecuteSilent(command) => write!(f, "execute-silent({command})"),
+            Action::Become(command) => write!(f, "become({command})"),
+            action => {
+                let name = ACTION_NAMES
+                    .iter()
+                    .find(|(_, named)| named == action)
+                    .map(|(name, _)| *name)
+                    .unwrap_or_default();
+                write!(f, "{name}")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn action_names_round_trip() {
+        for (name, action) in ACTION_NAMES {
+            assert_eq!(name.parse::<Action>().as_ref(), Ok(action));
+            assert_eq!(action.to_string(), *name);
+        }
+    }
+
+    #[test]
+    fn action_names_are_unique() {
+        for (index, (name, action)) in ACTION_NAMES.iter().enumerate() {
+            assert!(
+                ACTION_NAMES[index + 1..]
+                    .iter()
+                    .all(|(other_name, other_action)| other_name != name && other_action != action),
+                "{name} is listed twice"
+            );
+        }
+    }
+
+    #[test]
+    fn action_names_ignore_case_and_whitespace() {
+        assert_eq!(" Page-Up ".parse(), Ok(Action::PageUp));
+        assert!("page_up".parse::<Action>().is_err());
+        assert!("".parse::<Action>().is_err());
+    }
+
+    #[test]
+    fn command_actions_round_trip() {
+        for action in [
+            Action::Execute("vim {}".to_string()),
+            Action::ExecuteSilent("echo (a) {+}".to_string()),
+            Action::Become("less {1}".to_string()),
+        ] {
+            assert_eq!(action.to_string().parse(), Ok(action));
+        }
+        assert_eq!(
+            "Execute (rm {})".parse(),
+            Ok(Action::Execute("rm {}".to_string()))
+        );
+    }
+
+    #[test]
+    fn command_actions_need_a_command() {
+        assert!("execute()".parse::<Action>().is_err());
+        assert!("become(  )".parse::<Action>().is_err());
+        assert!("run(ls)".parse::<Action>().is_err());
+        assert!("execute(ls".parse::<Action>().is_err());
+    }
+}
@@ -2,13 +2,29 @@ extern crate picleo;
 
 use anyhow::Result;
 use clap::Parser;
-use picleo::{picker::Picker, requested_items::RequestedItems, selectable::SelectableItem};
+use picleo::{
+    action::Action,
+    byte_line::ByteLine,
+    command::{ExecutionMode, replace_process},
+    config::{Config, parse_delimiter},
+    keymap::KeyChord,
+    picker::{AppResult, Picker, PickerMode, PickerOutcome},
+    picker_item::{Column, PickerItem},
+    preview_layout::PreviewLayout,
+    requested_items::RequestedItems,
+    selectable::SelectableItem,
+    selected_items::SelectedItems,
+    source::command_source,
+};
+use serde_json::Value;
+use std::collections::HashMap;
 use std::{
     fmt, fs,
-    io::{self, BufRead},
+    io::{self, BufRead, Write},
     path::{Path, PathBuf},
+    process::{Command, ExitCode},
+    str::FromStr,
 };
-use std::collections::HashMap;
 
 // Wrapper for PathBuf that stores both full path and display string
 #[derive(Debug, Clone)]
@@ -32,11 +48,6 @@ impl DisplayPath {
             use_color,
         }
     }
-
-    fn simple(path: PathBuf, use_color: bool) -> Self {
-        let display_name = path.display().to_string();
-        Self::new(path, display_name, use_color)
-    }
 }
 
 impl fmt::Display for DisplayPath {
@@ -57,6 +68,90 @@ impl AsRef<PathBuf> for DisplayPath {
     }
 }
 
+/// JSON pointers that turn JSON Lines input into items
+#[derive(Debug, Clone)]
+struct JsonPointers {
+    /// Value shown in the item list, the whole line when not given
+    display: Option<String>,
+    /// Values matched as hidden columns
+    matched: Vec<String>,
+    /// Value printed when the item is accepted, the whole line when not given
+    output: Option<String>,
+}
+
+/// A line of JSON Lines input that is shown, matched and printed through JSON pointers
+#[derive(Debug, Clone)]
+struct JsonItem {
+    /// The original line
+    line: ByteLine,
+    /// Text of the displayed value followed by the matched values
+    columns: Vec<String>,
+    /// Value printed when the item is accepted, the original line when not set
+    output: Option<Vec<u8>>,
+}
+
+impl JsonItem {
+    fn new(line: ByteLine, pointers: &JsonPointers) -> Self {
+        // parsing the decoded text keeps lines with stray invalid UTF-8 usable, and lines that
+        // aren't valid JSON are still listed as their plain text
+        let Ok(value) = serde_json::from_str::<Value>(line.as_str()) else {
+            let mut columns = vec![line.as_str().to_string()];
+            columns.resize(pointers.matched.len() + 1, String::new());
+            return Self {
+                line,
+                columns,
+                output: None,
+            };
+        };
+
+        // missing values are treated as empty so the item can still be picked
+        let lookup = |pointer: &str| value.pointer(pointer).map(json_text).unwrap_or_default();
+        let display = match &pointers.display {
+            Some(pointer) => lookup(pointer),
+            None => line.as_str().to_string(),
+        };
+        let columns = std::iter::once(display)
+            .chain(pointers.matched.iter().map(|pointer| lookup(pointer)))
+            .collect();
+        let output = pointers
+            .output
+            .as_deref()
+            .map(|pointer| lookup(pointer).into_bytes());
+        Self {
+            line,
+            columns,
+            output,
+        }
+    }
+
+    /// Bytes printed when the item is accepted
+    fn output(&self) -> &[u8] {
+        self.output.as_deref().unwrap_or(self.line.as_bytes())
+    }
+}
+
+impl PickerItem for JsonItem {
+    fn columns(&self) -> Vec<String> {
+        self.columns.clone()
+    }
+
+    fn text(&self) -> String {
+        self.columns[0].clone()
+    }
+
+    fn is_lossy(&self) -> bool {
+        self.line.is_lossy()
+    }
+}
+
+/// Strings are used as they are, without quotes, other values as compact JSON
+fn json_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        value => value.to_string(),
+    }
+}
+
 /// Find the longest common path prefix among a list of paths
 fn find_common_prefix(paths: &[PathBuf]) -> PathBuf {
     if paths.is_empty() {
@@ -181,6 +276,24 @@ fn compute_display_names(paths: &[PathBuf]) -> Vec<(PathBuf, String)> {
     result
 }
 
+/// Exit status when nothing matched or nothing was accepted
+const EXIT_NO_MATCH: u8 = 1;
+
+/// Exit status when picleo failed
+const EXIT_ERROR: u8 = 2;
+
+/// Exit status when the user aborted, as for an interrupted program
+const EXIT_ABORTED: u8 = 130;
+
+/// How picleo ends once the picker is dropped
+enum Exit {
+    /// Exit with the status
+    Status(ExitCode),
+
+    /// Replace picleo with the command of a become action
+    Become(Command),
+}
+
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
@@ -200,6 +313,102 @@ struct Args {
     #[arg(short, long)]
     preview: Option<String>,
 
+    /// Run the preview command through a shell so pipes, functions and aliases work
+    #[arg(long)]
+    use_shell: bool,
+
+    /// Shell used to run commands, implies --use-shell, defaults to $SHELL
+    #[arg(long)]
+    shell: Option<String>,
+
+    /// Literal string separating the fields used by placeholders like {1}, defaults to whitespace
+    #[arg(short, long, conflicts_with = "delimiter_regex")]
+    delimiter: Option<String>,
+
+    /// Regular expression separating the fields used by placeholders like {1}
+    #[arg(long)]
+    delimiter_regex: Option<String>,
+
+    /// Comma separated names for the fields e.g. "path,line,text" for use as {path}, {line}, etc.
+    #[arg(long, value_delimiter = ',')]
+    columns: Option<Vec<String>>,
+
+    /// Use the first line of STDIN as the field names instead of an item
+    #[arg(long, conflicts_with_all = ["DIRS", "columns"])]
+    header: bool,
+
+    /// Load the items from the output of this command instead of STDIN, run again on reload. {q} is
+    /// the only placeholder, other braces are passed to the command unchanged
+    #[arg(long, conflicts_with_all = ["DIRS", "header"])]
+    source_cmd: Option<String>,
+
+    /// Pass the query to the source command as {q} on every change instead of filtering its output
+    #[arg(long, requires = "source_cmd")]
+    interactive: bool,
+
+    /// Preview pane layout e.g. "right,50%", "bottom,10,nowrap" or "left,hidden"
+    #[arg(long, value_parser = PreviewLayout::from_str)]
+    preview_window: Option<PreviewLayout>,
+
+    /// Bind a key to an action while searching e.g. "ctrl-e:execute(vim {})", can be repeated
+    #[arg(long, value_parser = parse_binding)]
+    bind: Vec<(KeyChord, Action)>,
+
+    /// Start with this query
+    #[arg(short, long)]
+    query: Option<String>,
+
+    /// Print the final query as the first line of the output
+    #[arg(long)]
+    print_query: bool,
+
+    /// Comma separated keys that accept the selection like enter, e.g. "ctrl-v,ctrl-x". The key
+    /// that was pressed is printed before the selected items, an empty line for enter
+    #[arg(long, value_delimiter = ',', value_parser = parse_expect_key)]
+    expect: Vec<(String, KeyChord)>,
+
+    /// Print the only matching item without starting the interface
+    #[arg(short = '1', long)]
+    select_1: bool,
+
+    /// Exit with status 1 without starting the interface when no items match
+    #[arg(short = '0', long)]
+    exit_0: bool,
+
+    /// Print the items matching this query without starting the interface
+    #[arg(short, long)]
+    filter: Option<String>,
+
+    /// Parse every line of STDIN as JSON, shown and printed as a whole unless --json-display or
+    /// --json-output pick a value
+    #[arg(long, conflicts_with_all = ["DIRS", "header", "source_cmd"])]
+    jsonl: bool,
+
+    /// JSON pointer of the value shown in the item list e.g. "/title"
+    #[arg(long, requires = "jsonl", value_parser = parse_json_pointer)]
+    json_display: Option<String>,
+
+    /// Comma separated JSON pointers of values matched as extra columns, named like
+    /// "node=/spec/nodeName" or after the last part of the pointer, searched with e.g. "node:foo"
+    #[arg(long, requires = "jsonl", value_delimiter = ',', value_parser = parse_json_column)]
+    json_match: Vec<(String, String)>,
+
+    /// JSON pointer of the value printed for accepted items e.g. "/id"
+    #[arg(long, requires = "jsonl", value_parser = parse_json_pointer)]
+    json_output: Option<String>,
+
+    /// Print at most this many items when filtering
+    #[arg(long, requires = "filter")]
+    limit: Option<usize>,
+
+    /// Read items from STDIN, files and the source command separated by NUL characters instead of newlines
+    #[arg(long)]
+    read0: bool,
+
+    /// End every line of output with a NUL character instead of a newline
+    #[arg(long)]
+    print0: bool,
+
     /// Keep ANSI color codes in preview output
     #[arg(long)]
     keep_colors: bool,
@@ -209,72 +418,243 @@ struct Args {
     no_color: bool,
 }
 
-fn main() -> Result<()> {
+/// Parse a key binding given as `chord:action`
+fn parse_binding(binding: &str) -> Result<(KeyChord, Action), String> {
+    // skip the first character so `:` itself can be bound
+    let separator = binding
+        .char_indices()
+        .skip(1)
+        .find(|(_, c)| *c == ':')
+        .map(|(index, _)| index)
+        .ok_or_else(|| {
+            format!("expected a binding like \"ctrl-e:execute(vim {{}})\", got {binding:?}")
+        })?;
+    Ok((
+        binding[..separator].parse()?,
+        binding[separator + 1..].parse()?,
+    ))
+}
+
+/// Parse an expected key, keeping its name for the output
+fn parse_expect_key(key: &str) -> Result<(String, KeyChord), String> {
+    Ok((key.trim().to_string(), key.parse()?))
+}
+
+/// Parse a JSON pointer like `/metadata/name`
+fn parse_json_pointer(pointer: &str) -> Result<String, String> {
+    if pointer.is_empty() || pointer.starts_with('/') {
+        Ok(pointer.to_string())
+    } else {
+        Err(format!("JSON pointer {pointer:?} must start with '/'"))
+    }
+}
+
+/// Parse a matched JSON pointer given as `name=pointer` or just `pointer`
+fn parse_json_column(column: &str) -> Result<(String, String), String> {
+    if let Some((name, pointer)) = column.split_once('=') {
+        return Ok((name.to_string(), parse_json_pointer(pointer)?));
+    }
+    let pointer = parse_json_pointer(column)?;
+    // name the column after the last reference token, unescaped as in RFC 6901
+    let name = pointer.rsplit('/').next().unwrap_or_default();
+    let name = name.replace("~1", "/").replace("~0", "~");
+    Ok((name, pointer))
+}
+
+/// Print the lines that precede the selected items, as requested by the arguments
+fn print_accept_info<T>(selected_items: &SelectedItems<T>, args: &Args) -> io::Result<()> {
+    if args.print_query {
+        print_record(selected_items.query().as_bytes(), args)?;
+    }
+    if !args.expect.is_empty() {
+        let key_name = selected_items.accepted_key().and_then(|accepted_key| {
+            args.expect
+                .iter()
+                .find(|(_, key)| *key == accepted_key)
+                .map(|(name, _)| name.as_str())
+        });
+        print_record(key_name.unwrap_or_default().as_bytes(), args)?;
+    }
+    Ok(())
+}
+
+/// Print one line of output, or one NUL terminated record with `--print0`
+///
+/// Records are written as raw bytes so items that weren't valid UTF-8 come out unchanged.
+fn print_record(record: &[u8], args: &Args) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(record)?;
+    stdout.write_all(if args.print0 { b"\0" } else { b"\n" })
+}
+
+fn main() -> ExitCode {
     let args = Args::parse();
 
+    match run(args) {
+        Ok(Exit::Status(exit_code)) => exit_code,
+        // the picker is gone by now, so its temporary files were removed before picleo is replaced
+        Ok(Exit::Become(command)) => {
+            let err = replace_process(command);
+            eprintln!("picleo: failed to run the become command: {err}");
+            ExitCode::from(EXIT_ERROR)
+        }
+        Err(err) => {
+            // stdout is usually captured as the selection, so errors must not end up there
+            eprintln!("picleo: {err:#}");
+            ExitCode::from(EXIT_ERROR)
+        }
+    }
+}
+
+/// Read the config, then load the items and run the picker
+fn run(args: Args) -> Result<Exit> {
+    // the config is read once so the whole run uses the same settings, and checked here since the
+    // picker falls back to the defaults for a config it can't use
+    let config = Config::load()?;
+    config
+        .validate()
+        .map_err(|e| anyhow::anyhow!("invalid config: {e}"))?;
+
     // Load items
     if !args.dirs.is_empty() {
-        load_from_args(args)?
+        load_from_args(args, config)
     } else {
-        load_from_stdin(args)?
+        load_from_stdin(args, config)
     }
+}
+
+/// Create a picker from the config read for this run
+fn build_picker<T>(config: Config, columns: Vec<Column>) -> Result<Picker<T>>
+where
+    T: Sync + Send + PickerItem,
+{
+    Picker::builder()
+        .config(config)
+        .columns(columns)
+        .build()
+        .map_err(|err| anyhow::anyhow!("{err}"))
+}
 
+/// Apply the settings shared by every kind of picker
+fn configure_picker<T>(picker: &mut Picker<T>, args: &Args) -> Result<()>
+where
+    T: Sync + Send + PickerItem,
+{
+    if let Some(preview_cmd) = args.preview.clone() {
+        picker.set_preview_command(preview_cmd);
+    }
+    if let Some(preview_layout) = args.preview_window {
+        picker.set_preview_layout(preview_layout);
+    }
+    if args.use_shell || args.shell.is_some() {
+        let shell = args.shell.clone().unwrap_or_else(|| picker.config.shell());
+        picker.set_execution_mode(ExecutionMode::Shell(shell));
+    }
+    if args.delimiter.is_some() || args.delimiter_regex.is_some() {
+        let delimiter = parse_delimiter(args.delimiter.as_deref(), args.delimiter_regex.as_deref())
+            .map_err(|e| anyhow::anyhow!(e))?;
+        picker.set_delimiter(delimiter);
+    }
+    if let Some(field_names) = args.columns.clone() {
+        picker.set_field_names(field_names);
+    }
+    if let Some(query) = args.query.clone() {
+        picker.set_query(query);
+    }
+    picker.set_expect_keys(args.expect.iter().map(|(_, key)| *key).collect());
+    picker.set_select_one(args.select_1);
+    picker.set_exit_zero(args.exit_0);
+    for (chord, action) in &args.bind {
+        picker
+            .keymap
+            .bind(PickerMode::Search, *chord, action.clone());
+    }
     Ok(())
 }
 
-fn load_from_args(args: Args) -> Result<(), anyhow::Error> {
+/// Run the picker, or only match the items against the query when filtering
+fn pick<'a, T>(picker: &'a mut Picker<T>, args: &Args) -> AppResult<PickerOutcome<'a, T>>
+where
+    T: Sync + Send + PickerItem,
+{
+    match &args.filter {
+        Some(query) => {
+            let matches = picker.filter(query, args.limit);
+            if matches.items().is_empty() {
+                Ok(PickerOutcome::NoMatch)
+            } else {
+                Ok(PickerOutcome::Accepted(matches))
+            }
+        }
+        None => picker.run(),
+    }
+}
+
+/// Print the accepted items, formatting existing ones with `format_item`, and return the exit status
+fn finish<T>(
+    outcome: AppResult<PickerOutcome<'_, T>>,
+    args: &Args,
+    format_item: impl Fn(&T) -> &[u8],
+) -> Result<Exit> {
+    match outcome.map_err(|err| anyhow::anyhow!("{err}"))? {
+        PickerOutcome::Accepted(selected_items) => {
+            print_accept_info(&selected_items, args)?;
+            for item in selected_items.existing_values() {
+                print_record(format_item(item), args)?;
+            }
+            for requested_item in selected_items.requested_values() {
+                print_record(requested_item.as_bytes(), args)?;
+            }
+
+            // accepting while nothing matched selects nothing
+            if selected_items.items().is_empty() {
+                Ok(Exit::Status(ExitCode::from(EXIT_NO_MATCH)))
+            } else {
+                Ok(Exit::Status(ExitCode::SUCCESS))
+            }
+        }
+        PickerOutcome::NoMatch => Ok(Exit::Status(ExitCode::from(EXIT_NO_MATCH))),
+        PickerOutcome::Aborted => Ok(Exit::Status(ExitCode::from(EXIT_ABORTED))),
+        PickerOutcome::Become(command) => Ok(Exit::Become(command)),
+    }
+}
+
+fn load_from_args(args: Args, config: Config) -> Result<Exit> {
     let has_files = args.dirs.iter().any(|path| path.is_file());
     let has_dirs = args.dirs.iter().any(|path| path.is_dir());
 
     // these are here to prevent lifetime issues since args is a reference, error: borrowed data escapes outside of function
     let dirs = args.dirs.clone();
-    let preview_command = args.preview;
 
     // Check if we have any files vs directories to determine picker type
     if has_files && !has_dirs {
-        // Only files - use String picker for file contents
-        let mut picker = Picker::<String>::new(true);
+        // Only files - use ByteLine picker for file contents
+        let mut picker = build_picker::<ByteLine>(config, vec![Column::default()])?;
         picker.set_keep_colors(args.keep_colors);
-        if let Some(preview_cmd) = preview_command.clone() {
-            picker.set_preview_command(preview_cmd);
-        }
+        configure_picker(&mut picker, &args)?;
 
+        let read0 = args.read0;
         for file_path in dirs {
             if file_path.is_file() {
                 if args.threaded {
                     picker.inject_items_threaded(move |i| {
-                        read_file_lines(&file_path, i);
+                        read_file_lines(&file_path, read0, i);
                     });
                 } else {
                     picker.inject_items(|i| {
-                        read_file_lines(&file_path, i);
+                        read_file_lines(&file_path, read0, i);
                     });
                 }
             }
         }
 
         // Run app
-        match picker.run() {
-            Ok(selected_items) => {
-                for line in selected_items.existing_values() {
-                    println!("{}", line)
-                }
-                for requested_line in selected_items.requested_values() {
-                    println!("{}", requested_line)
-                }
-            }
-            Err(err) => {
-                println!("{err:?}");
-                return Err(anyhow::anyhow!("{:?}", err));
-            }
-        }
+        finish(pick(&mut picker, &args), &args, ByteLine::as_bytes)
     } else {
         // Has directories or mixed - use DisplayPath picker for file paths
-        let mut picker = Picker::<DisplayPath>::new(true);
+        let mut picker = build_picker::<DisplayPath>(config, vec![Column::default()])?;
         picker.set_keep_colors(args.keep_colors || !args.no_color);
-        if let Some(preview_cmd) = preview_command {
-            picker.set_preview_command(preview_cmd);
-        }
+        configure_picker(&mut picker, &args)?;
 
         // Collect directories to use for completion sources
         let completion_dirs: Vec<PathBuf> = args
@@ -289,51 +669,49 @@ fn load_from_args(args: Args) -> Result<(), anyhow::Error> {
             // Create the completion suggestions vec and add the default entry i.e. what the user typed at the default path
             let mut suggestions = RequestedItems::from_vec(vec![match completion_dirs.first() {
                 Some(dir) => SelectableItem::new_requested(
-                    dir.join(query.to_string()).to_string_lossy().to_string(),
+                    dir.join(query).to_string_lossy().to_string(),
                 ),
                 None => SelectableItem::new_requested(query.to_string()),
             }]);
 
             // Split query as file path
             let path_to_match = Path::new(query);
-            match (path_to_match.parent(), path_to_match.file_name()) {
-                // Perform completion with the given path and file name
-                (Some(parent), Some(file_name)) => {
-                    // Add completions for all provided directories
-                    for dir in &completion_dirs {
-                        // Check if the path, joined with the user provided text exists and is a directory
-                        let new_path = dir.join(parent);
-                        if new_path.exists() && new_path.is_dir() {
-                            // Read the directory entries
-                            if let Ok(files) = fs::read_dir(dir) {
-                                // Add completion suggestions
-                                suggestions.extend(files.filter_map(|entry| {
-                                    // Ignore errors in individual directory entries
-                                    entry.ok().and_then(|e| {
-                                        match e
-                                            .file_name()
-                                            .to_string_lossy()
-                                            .starts_with(&file_name.to_string_lossy().to_string())
-                                        {
-                                            // Add any directory entries that have a matching prefix
-                                            true => {
-                                                let mut parent_path = dir.clone();
-                                                parent_path.push(e.file_name());
-                                                Some(SelectableItem::new_requested(
-                                                    parent_path.to_string_lossy().to_string(),
-                                                ))
-                                            }
-                                            false => None,
+            // Perform completion with the given path and file name
+            // seems like other cases are not important and we can ignore them (at least in testing)
+            if let (Some(parent), Some(file_name)) =
+                (path_to_match.parent(), path_to_match.file_name())
+            {
+                // Add completions for all provided directories
+                for dir in &completion_dirs {
+                    // Check if the path, joined with the user provided text exists and is a directory
+                    let new_path = dir.join(parent);
+                    if new_path.exists() && new_path.is_dir() {
+                        // Read the directory entries
+                        if let Ok(files) = fs::read_dir(dir) {
+                            // Add completion suggestions
+                            suggestions.extend(files.filter_map(|entry| {
+                                // Ignore errors in individual directory entries
+                                entry.ok().and_then(|e| {
+                                    match e
+                                        .file_name()
+                                        .to_string_lossy()
+                                        .starts_with(&file_name.to_string_lossy().to_string())
+                                    {
+                                        // Add any directory entries that have a matching prefix
+                                        true => {
+                                            let mut parent_path = dir.clone();
+                                            parent_path.push(e.file_name());
+                                            Some(SelectableItem::new_requested(
+                                                parent_path.to_string_lossy().to_string(),
+                                            ))
                                         }
-                                    })
-                                }));
-                            }
+                                        false => None,
+                                    }
+                                })
+                            }));
                         }
                     }
                 }
-
-                // seems like other cases are not important and we can ignore them (at least in testing)
-                _ => {}
             }
 
             suggestions
@@ -370,15 +748,17 @@ fn load_from_args(args: Args) -> Result<(), anyhow::Error> {
 
                 if args.threaded {
                     picker.inject_items_threaded(move |i| {
-                        i.push(SelectableItem::new(display_path), |item, columns| {
-                            columns[0] = item.to_string().into()
-                        });
+                        i.push(
+                            SelectableItem::new(display_path),
+                            SelectableItem::fill_columns,
+                        );
                     });
                 } else {
                     picker.inject_items(|i| {
-                        i.push(SelectableItem::new(display_path.clone()), |item, columns| {
-                            columns[0] = item.to_string().into()
-                        });
+                        i.push(
+                            SelectableItem::new(display_path.clone()),
+                            SelectableItem::fill_columns,
+                        );
                     });
                 }
             } else if path.is_dir() {
@@ -406,71 +786,88 @@ fn load_from_args(args: Args) -> Result<(), anyhow::Error> {
         }
 
         // Run app
-        match picker.run() {
-            Ok(selected_items) => {
-                for path in selected_items.existing_values() {
-                    // Print the full absolute path
-                    println!("{}", path.full_path.display())
-                }
-                for requested_path in selected_items.requested_values() {
-                    println!("{}", requested_path)
-                }
-            }
-            Err(err) => {
-                println!("{err:?}");
-                return Err(anyhow::anyhow!("{:?}", err));
-            }
-        }
+        // Print the full absolute path
+        finish(pick(&mut picker, &args), &args, |path| {
+            path.full_path.as_os_str().as_encoded_bytes()
+        })
     }
-    Ok(())
 }
 
-fn load_from_stdin(args: Args) -> Result<(), anyhow::Error> {
-    let mut picker = Picker::<String>::new(true);
+fn load_from_stdin(args: Args, config: Config) -> Result<Exit> {
+    if args.jsonl {
+        return load_json_from_stdin(args, config);
+    }
+    let mut picker = build_picker::<ByteLine>(config, vec![Column::default()])?;
     picker.set_keep_colors(args.keep_colors);
-    if let Some(preview_cmd) = args.preview {
-        picker.set_preview_command(preview_cmd);
+    configure_picker(&mut picker, &args)?;
+    if args.header {
+        // the header names the fields rather than being an item itself
+        let mut header = Vec::new();
+        let terminator = if args.read0 { b'\0' } else { b'\n' };
+        io::stdin().lock().read_until(terminator, &mut header)?;
+        let header = String::from_utf8_lossy(&header);
+        let field_names = picker
+            .delimiter
+            .fields(header.trim_end_matches(['\0', '\n', '\r']))
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+        picker.set_field_names(field_names);
     }
-    if args.threaded {
-        picker.inject_items_threaded(|i| {
+    if let Some(source_cmd) = args.source_cmd.clone() {
+        let execution_mode = picker.execution_mode.clone();
+        picker.set_interactive(args.interactive);
+        picker.set_source(command_source(source_cmd, execution_mode, args.read0));
+    } else if args.threaded {
+        let read0 = args.read0;
+        picker.inject_items_threaded(move |i| {
             // Read from stdin
-            // TODO: might want to handle read errors from stdin
-            for line in io::stdin().lock().lines().map_while(Result::ok) {
-                i.push(SelectableItem::new(line), |item, columns| {
-                    columns[0] = item.to_string().into()
-                });
-            }
+            read_items(io::stdin().lock(), read0, i);
         });
     } else {
         picker.inject_items(|i| {
             // Read from stdin
-            // TODO: might want to handle read errors from stdin
-            for line in io::stdin().lock().lines().map_while(Result::ok) {
-                i.push(SelectableItem::new(line), |item, columns| {
-                    columns[0] = item.to_string().into()
-                });
-            }
+            read_items(io::stdin().lock(), args.read0, i);
         });
     }
     // Create app state
 
     // Run app
-    match picker.run() {
-        Ok(selected_items) => {
-            for line in selected_items.existing_values() {
-                println!("{}", line)
-            }
-            for requested_line in selected_items.requested_values() {
-                println!("{}", requested_line)
-            }
-        }
-        Err(err) => {
-            println!("{err:?}");
-            return Err(anyhow::anyhow!("{:?}", err));
-        }
+    finish(pick(&mut picker, &args), &args, ByteLine::as_bytes)
+}
+
+/// Load JSON Lines from STDIN, matching the values picked by the JSON pointer arguments
+fn load_json_from_stdin(args: Args, config: Config) -> Result<Exit> {
+    let pointers = JsonPointers {
+        display: args.json_display.clone(),
+        matched: args
+            .json_match
+            .iter()
+            .map(|(_, pointer)| pointer.clone())
+            .collect(),
+        output: args.json_output.clone(),
+    };
+
+    // the displayed value is searched by default, matched values by their column name
+    let columns = std::iter::once(Column::default())
+        .chain(args.json_match.iter().map(|(name, _)| Column::hidden(name)))
+        .collect();
+    let mut picker = build_picker::<JsonItem>(config, columns)?;
+    picker.set_keep_colors(args.keep_colors);
+    configure_picker(&mut picker, &args)?;
+
+    if args.threaded {
+        let read0 = args.read0;
+        picker.inject_items_threaded(move |i| {
+            read_json_items(io::stdin().lock(), read0, &pointers, i);
+        });
+    } else {
+        picker.inject_items(|i| {
+            read_json_items(io::stdin().lock(), args.read0, &pointers, i);
+        });
     }
 
-    Ok(())
+    finish(pick(&mut picker, &args), &args, JsonItem::output)
 }
 
 /// Collect all file paths from a directory (used for computing common prefix)
@@ -481,7 +878,7 @@ fn collect_paths_from_dir(dir: &PathBuf, recursive: bool, paths: &mut Vec<PathBu
             if path.is_dir() && recursive {
                 collect_paths_from_dir(&path, recursive, paths);
             } else if path.is_file() {
-                let abs_path = fs::canonicalize(&path).unwrap_or_else(|_| path);
+                let abs_path = fs::canonicalize(&path).unwrap_or(path);
                 paths.push(abs_path);
             }
         }
@@ -497,15 +894,13 @@ fn walk_dir_with_display(
     if let Ok(entries) = fs::read_dir(dir) {
         for entry in entries.flatten() {
             let path = entry.path();
-            let abs_path = fs::canonicalize(&path).unwrap_or_else(|_| path);
+            let abs_path = fs::canonicalize(&path).unwrap_or(path);
             let display_name = path_to_display
                 .get(&abs_path)
                 .cloned()
                 .unwrap_or_else(|| abs_path.display().to_string());
             let display_path = DisplayPath::new(abs_path, display_name, use_color);
-            i.push(SelectableItem::new(display_path), |item, columns| {
-                columns[0] = item.to_string().into()
-            });
+            i.push(SelectableItem::new(display_path), SelectableItem::fill_columns);
         }
     }
 }
@@ -522,26 +917,76 @@ fn walk_dir_recursive_with_display(
             if path.is_dir() {
                 walk_dir_recursive_with_display(&path, injector, path_to_display, use_color);
             } else {
-                let abs_path = fs::canonicalize(&path).unwrap_or_else(|_| path);
+                let abs_path = fs::canonicalize(&path).unwrap_or(path);
                 let display_name = path_to_display
                     .get(&abs_path)
                     .cloned()
                     .unwrap_or_else(|| abs_path.display().to_string());
                 let display_path = DisplayPath::new(abs_path, display_name, use_color);
-                injector.push(SelectableItem::new(display_path), |item, columns| {
-                    columns[0] = item.to_string().into()
-                });
+                injector.push(
+                    SelectableItem::new(display_path),
+                    SelectableItem::fill_columns,
+                );
             }
         }
     }
 }
 
-fn read_file_lines(file_path: &PathBuf, injector: &nucleo::Injector<SelectableItem<String>>) {
-    if let Ok(contents) = fs::read_to_string(file_path) {
-        for line in contents.lines() {
-            injector.push(SelectableItem::new(line.to_string()), |item, columns| {
-                columns[0] = item.to_string().into()
-            });
+fn read_file_lines(
+    file_path: &PathBuf,
+    read0: bool,
+    injector: &nucleo::Injector<SelectableItem<ByteLine>>,
+) {
+    if let Ok(file) = fs::File::open(file_path) {
+        read_items(io::BufReader::new(file), read0, injector);
+    }
+}
+
+/// Add every line as an item, or every NUL terminated record with `read0`
+///
+/// Items keep their original bytes, input that isn't valid UTF-8 is matched on a lossy decoding.
+fn read_items(
+    reader: impl BufRead,
+    read0: bool,
+    injector: &nucleo::Injector<SelectableItem<ByteLine>>,
+) {
+    for record in records(reader, read0) {
+        injector.push(
+            SelectableItem::new(ByteLine::new(record)),
+            SelectableItem::fill_columns,
+        );
+    }
+}
+
+/// Add every non-empty line as a JSON item, or every NUL terminated record with `read0`
+fn read_json_items(
+    reader: impl BufRead,
+    read0: bool,
+    pointers: &JsonPointers,
+    injector: &nucleo::Injector<SelectableItem<JsonItem>>,
+) {
+    for record in records(reader, read0) {
+        if record.iter().all(u8::is_ascii_whitespace) {
+            continue;
         }
+        injector.push(
+            SelectableItem::new(JsonItem::new(ByteLine::new(record), pointers)),
+            SelectableItem::fill_columns,
+        );
     }
 }
+
+/// Split the input into lines without their line ending, or into NUL terminated records
+fn records(reader: impl BufRead, read0: bool) -> impl Iterator<Item = Vec<u8>> {
+    // TODO: might want to handle read errors
+    let terminator = if read0 { b'\0' } else { b'\n' };
+    reader
+        .split(terminator)
+        .map_while(Result::ok)
+        .map(move |mut record| {
+            if !read0 && record.last() == Some(&b'\r') {
+                record.pop();
+            }
+            record
+        })
+}
@@ -0,0 +1,81 @@
+use crate::picker_item::PickerItem;
+
+/// A line of input that keeps its original bytes, even when they aren't valid UTF-8
+///
+/// Matching and display use a lossy decoding of the bytes, with invalid sequences replaced by
+/// `U+FFFD`, while [`ByteLine::as_bytes`] returns the line exactly as it was read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ByteLine {
+    text: String,
+    /// The original bytes, only kept when they weren't valid UTF-8 and differ from `text`
+    raw: Option<Vec<u8>>,
+}
+
+impl ByteLine {
+    /// Create a line from raw bytes, decoding them for display
+    pub fn new(bytes: Vec<u8>) -> Self {
+        // valid lines take over the bytes as their text, so only lossy lines are stored twice
+        match String::from_utf8(bytes) {
+            Ok(text) => Self { text, raw: None },
+            Err(err) => {
+                let bytes = err.into_bytes();
+                Self {
+                    text: String::from_utf8_lossy(&bytes).into_owned(),
+                    raw: Some(bytes),
+                }
+            }
+        }
+    }
+
+    /// Returns the original bytes of the line
+    pub fn as_bytes(&self) -> &[u8] {
+        self.raw.as_deref().unwrap_or(self.text.as_bytes())
+    }
+
+    /// Returns the decoded text of the line
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+}
+
+impl From<String> for ByteLine {
+    fn from(text: String) -> Self {
+        Self { text, raw: None }
+    }
+}
+
+impl PickerItem for ByteLine {
+    fn columns(&self) -> Vec<String> {
+        vec![self.text.clone()]
+    }
+
+    fn text(&self) -> String {
+        self.text.clone()
+    }
+
+    fn is_lossy(&self) -> bool {
+        self.raw.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_lines_are_only_stored_as_text() {
+        let line = ByteLine::new("café".as_bytes().to_vec());
+        assert_eq!(line.as_str(), "café");
+        assert_eq!(line.as_bytes(), "café".as_bytes());
+        assert!(line.raw.is_none());
+        assert!(!line.is_lossy());
+    }
+
+    #[test]
+    fn invalid_lines_keep_their_bytes() {
+        let line = ByteLine::new(b"caf\xe9".to_vec());
+        assert_eq!(line.as_str(), "caf\u{fffd}");
+        assert_eq!(line.as_bytes(), b"caf\xe9");
+        assert!(line.is_lossy());
+    }
+}
@@ -0,0 +1,124 @@
+use crate::placeholders::Placeholders;
+use comma::parse_command;
+use std::{
+    fs::{File, OpenOptions},
+    io,
+    process::{Child, Command, ExitStatus, Stdio},
+    thread,
+};
+
+/// How commands given on the command line or in the config are started
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExecutionMode {
+    /// Split the command into arguments and run the program directly
+    Direct,
+
+    /// Pass the whole command to this shell with `-c`, allowing pipes, functions and aliases
+    Shell(String),
+}
+
+impl ExecutionMode {
+    /// Create a command from a template, filling in its placeholders
+    ///
+    /// Returns `None` if the template doesn't contain a command.
+    pub(crate) fn command(&self, template: &str, placeholders: &Placeholders) -> Option<Command> {
+        match self {
+            ExecutionMode::Direct => {
+                let command_parts = parse_command(template)?;
+                let mut command_parts_iter = command_parts.iter();
+                let program = command_parts_iter.next()?;
+
+                // we are substituting args separately to minimize whitespace issues
+                let mut command = Command::new(program);
+                command.args(command_parts_iter.flat_map(|arg| placeholders.arguments(arg)));
+                Some(command)
+            }
+            ExecutionMode::Shell(shell) => {
+                if template.trim().is_empty() {
+                    return None;
+                }
+
+                // values are escaped since the shell parses the whole command line
+                let mut command = Command::new(shell);
+                command
+                    .arg("-c")
+                    .arg(placeholders.substitute(template, true));
+                Some(command)
+            }
+        }
+    }
+}
+
+/// Run a command attached to the terminal and wait for it to exit
+///
+/// The picker's stdin and stdout are often pipes feeding it items and reading its output, so the
+/// command is connected to the controlling terminal instead where there is one.
+pub(crate) fn run_in_foreground(mut command: Command) -> io::Result<ExitStatus> {
+    if let Ok(tty) = File::open("/dev/tty") {
+        command.stdin(tty);
+    }
+    if let Ok(tty) = OpenOptions::new().write(true).open("/dev/tty") {
+        command.stdout(tty);
+    }
+    command.status()
+}
+
+/// Start a command without any input or output and don't wait for it
+pub(crate) fn run_in_background(mut command: Command) -> io::Result<()> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    // reap the process once it exits
+    thread::spawn(move || child.wait());
+    Ok(())
+}
+
+/// Start a command in a process group of its own
+///
+/// Killing the command with [`kill_process_group`] then also stops the processes it started, like
+/// the parts of a shell pipeline, which would otherwise keep its output open.
+pub(crate) fn spawn_process_group(command: &mut Command) -> io::Result<Child> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+
+        command.process_group(0);
+    }
+    command.spawn()
+}
+
+/// Kill a command started with [`spawn_process_group`] together with everything it started
+pub(crate) fn kill_process_group(child: &mut Child) {
+    #[cfg(unix)]
+    if let Ok(group) = libc::pid_t::try_from(child.id()) {
+        // SAFETY: killpg only sends a signal, the group id is the pid of our own child
+        unsafe {
+            libc::killpg(group, libc::SIGKILL);
+        }
+    }
+    let _ = child.kill();
+}
+
+/// Replace the current process with the command
+///
+/// Only returns if the command couldn't be started.
+#[cfg(unix)]
+pub fn replace_process(mut command: Command) -> io::Error {
+    use std::os::unix::process::CommandExt;
+
+    command.exec()
+}
+
+/// Run the command in place of the current process and exit with its status
+///
+/// Only returns if the command couldn't be started.
+#[cfg(not(unix))]
+pub fn replace_process(mut command: Command) -> io::Error {
+    match command.status() {
+        Ok(status) => std::process::exit(status.code().unwrap_or(1)),
+        Err(e) => e,
+    }
+}
@@ -1,9 +1,23 @@
+use crate::{
+    command::ExecutionMode,
+    keymap::Keymap,
+    picker::PickerMode,
+    placeholders::{Delimiter, Placeholders},
+    preview_layout::PreviewLayout,
+};
 use figment::{
-    providers::{Env, Format, Json, Toml, Yaml},
     Figment,
+    providers::{Env, Format, Json, Toml, Yaml},
 };
+use nucleo::pattern::{CaseMatching, Normalization};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::{
+    collections::BTreeMap,
+    env,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -27,6 +41,36 @@ pub struct Config {
 
     /// Default preview command
     pub preview_command: Option<String>,
+
+    /// Number of preview outputs to keep cached, 0 disables the cache
+    pub preview_cache_size: Option<usize>,
+
+    /// Milliseconds that a cached preview output stays valid
+    pub preview_cache_ttl: Option<u64>,
+
+    /// Milliseconds to wait after the query stops changing before reloading in interactive mode
+    pub reload_delay: Option<u64>,
+
+    /// Run commands through a shell instead of executing them directly
+    pub use_shell: Option<bool>,
+
+    /// Shell used to run commands, defaults to `$SHELL`
+    pub shell: Option<String>,
+
+    /// Literal string separating the fields of an item, defaults to whitespace
+    pub delimiter: Option<String>,
+
+    /// Regular expression separating the fields of an item, used instead of `delimiter`
+    pub delimiter_regex: Option<String>,
+
+    /// Names for the fields of an item, usable as placeholders e.g. `{path}`
+    pub field_names: Option<Vec<String>>,
+
+    /// Key bindings that replace or add to the defaults
+    pub keys: Option<KeyBindings>,
+
+    /// Preview pane layout, e.g. `right,50%,wrap` or `bottom,10,hidden`
+    pub preview_window: Option<String>,
 }
 
 impl Default for Config {
@@ -39,12 +83,23 @@ impl Default for Config {
             wrap_around: Some(true),
             invert_scroll: Some(false),
             preview_command: None,
+            preview_cache_size: Some(32),
+            preview_cache_ttl: Some(5000),
+            reload_delay: Some(150),
+            preview_window: None,
+            keys: None,
+            delimiter: None,
+            delimiter_regex: None,
+            field_names: None,
+            use_shell: Some(false),
+            shell: None,
         }
     }
 }
 
 impl Config {
     /// Load configuration from platform-appropriate config directories
+    #[allow(clippy::result_large_err)]
     pub fn load() -> Result<Self, figment::Error> {
         let mut figment = Figment::new();
 
@@ -70,7 +125,7 @@ impl Config {
         figment.extract()
     }
 
-    fn add_config_files(mut figment: Figment, base_path: &PathBuf) -> Figment {
+    fn add_config_files(mut figment: Figment, base_path: &Path) -> Figment {
         // Try different config file formats
         for extension in &["toml", "yaml", "yml", "json"] {
             let config_file = base_path.with_extension(extension);
@@ -110,4 +165,232 @@ impl Config {
     pub fn preview_command(&self) -> Option<&String> {
         self.preview_command.as_ref()
     }
+
+    /// Get the preview cache size, falling back to default if not configured
+    pub fn preview_cache_size(&self) -> usize {
+        self.preview_cache_size.unwrap_or(32)
+    }
+
+    /// Get the preview cache expiry, falling back to default if not configured
+    pub fn preview_cache_ttl(&self) -> Duration {
+        Duration::from_millis(self.preview_cache_ttl.unwrap_or(5000))
+    }
+
+    /// Get the delay before reloading after a query change, falling back to default if not configured
+    pub fn reload_delay(&self) -> Duration {
+        Duration::from_millis(self.reload_delay.unwrap_or(150))
+    }
+
+    /// Get the shell used to run commands, falling back to `$SHELL` and then `sh`
+    pub fn shell(&self) -> String {
+        self.shell
+            .clone()
+            .or_else(|| env::var("SHELL").ok())
+            .filter(|shell| !shell.is_empty())
+            .unwrap_or_else(|| "sh".to_string())
+    }
+
+    /// Get how commands are run, directly unless the shell is enabled
+    pub fn execution_mode(&self) -> ExecutionMode {
+        if self.use_shell.unwrap_or(false) {
+            ExecutionMode::Shell(self.shell())
+        } else {
+            ExecutionMode::Direct
+        }
+    }
+
+    /// Get the field delimiter, falling back to whitespace if not configured or invalid
+    pub fn delimiter(&self) -> Delimiter {
+        parse_delimiter(self.delimiter.as_deref(), self.delimiter_regex.as_deref())
+            .unwrap_or_default()
+    }
+
+    /// Get the keymap, the defaults with the configured bindings applied
+    ///
+    /// Returns a description of the first invalid binding rather than leaving it out.
+    pub fn keymap(&self) -> Result<Keymap, String> {
+        let mut keymap = Keymap::default();
+        if let Some(keys) = &self.keys {
+            for (mode, chord, action) in keys.bindings() {
+                keymap
+                    .bind_names(mode, chord, action)
+                    .map_err(|e| format!("invalid key binding {chord:?} = {action:?}: {e}"))?;
+            }
+        }
+        Ok(keymap)
+    }
+
+    /// Get the preview pane layout, falling back to the default layout if not configured or invalid
+    pub fn preview_layout(&self) -> PreviewLayout {
+        self.preview_window
+            .as_deref()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or_default()
+    }
+
+    /// Get the case matching setting, falling back to smart case if not configured or invalid
+    pub fn case_matching(&self) -> CaseMatching {
+        self.case_matching
+            .as_deref()
+            .and_then(|value| parse_case_matching(value).ok())
+            .unwrap_or(CaseMatching::Smart)
+    }
+
+    /// Get the normalization setting, falling back to smart normalization if not configured or invalid
+    pub fn normalization(&self) -> Normalization {
+        self.normalization
+            .as_deref()
+            .and_then(|value| parse_normalization(value).ok())
+            .unwrap_or(Normalization::Smart)
+    }
+
+    /// Check that all configured values can be used, returning a description of the first invalid one
+    pub fn validate(&self) -> Result<(), String> {
+        if self.height == Some(0) {
+            return Err("height must be greater than zero".to_string());
+        }
+        if let Some(case_matching) = &self.case_matching {
+            parse_case_matching(case_matching)?;
+        }
+        if let Some(normalization) = &self.normalization {
+            parse_normalization(normalization)?;
+        }
+        parse_delimiter(self.delimiter.as_deref(), self.delimiter_regex.as_deref())?;
+        if let Some(field_names) = &self.field_names
+            && field_names.iter().any(|name| name.is_empty())
+        {
+            return Err("field names must not be empty".to_string());
+        }
+        self.keymap()?;
+        if let Some(preview_window) = &self.preview_window {
+            preview_window.parse::<PreviewLayout>()?;
+        }
+        if let Some(preview_command) = &self.preview_command
+            && self
+                .execution_mode()
+                .command(preview_command, &Placeholders::default())
+                .is_none()
+        {
+            return Err(format!("invalid preview command: {preview_command:?}"));
+        }
+        Ok(())
+    }
+}
+
+/// Key bindings for each mode, mapping key chords like `ctrl-h` to actions like `backward-delete-char`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyBindings {
+    /// Bindings used while searching
+    pub search: BTreeMap<String, String>,
+
+    /// Bindings used while editing an item
+    pub editing: BTreeMap<String, String>,
+
+    /// Bindings used on the help screen
+    pub help: BTreeMap<String, String>,
+}
+
+impl KeyBindings {
+    /// Returns every binding along with the mode it applies to
+    fn bindings(&self) -> impl Iterator<Item = (PickerMode, &str, &str)> {
+        [
+            (PickerMode::Search, &self.search),
+            (PickerMode::Editing, &self.editing),
+            (PickerMode::Help, &self.help),
+        ]
+        .into_iter()
+        .flat_map(|(mode, bindings)| {
+            bindings
+                .iter()
+                .map(move |(chord, action)| (mode, chord.as_str(), action.as_str()))
+        })
+    }
+}
+
+/// Create a field delimiter from either a literal string or a regular expression
+pub fn parse_delimiter(literal: Option<&str>, regex: Option<&str>) -> Result<Delimiter, String> {
+    match (literal, regex) {
+        (Some(_), Some(_)) => {
+            Err("only one of delimiter and delimiter_regex can be set".to_string())
+        }
+        (Some(""), None) => Err("delimiter must not be empty".to_string()),
+        (Some(literal), None) => Ok(Delimiter::Literal(literal.to_string())),
+        (None, Some(regex)) => Regex::new(regex)
+            .map(Delimiter::Regex)
+            .map_err(|e| format!("invalid delimiter regex {regex:?}: {e}")),
+        (None, None) => Ok(Delimiter::Whitespace),
+    }
+}
+
+/// Parse a case matching name, one of `smart`, `ignore`, or `respect`
+pub fn parse_case_matching(value: &str) -> Result<CaseMatching, String> {
+    match value.to_lowercase().as_str() {
+        "smart" => Ok(CaseMatching::Smart),
+        "ignore" => Ok(CaseMatching::Ignore),
+        "respect" => Ok(CaseMatching::Respect),
+        _ => Err(format!(
+            "invalid case matching {value:?}, expected one of: smart, ignore, respect"
+        )),
+    }
+}
+
+/// Parse a normalization name, one of `smart` or `never`
+pub fn parse_normalization(value: &str) -> Result<Normalization, String> {
+    match value.to_lowercase().as_str() {
+        "smart" => Ok(Normalization::Smart),
+        "never" => Ok(Normalization::Never),
+        _ => Err(format!(
+            "invalid normalization {value:?}, expected one of: smart, never"
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::Action;
+
+    fn config_with_search_keys(bindings: &[(&str, &str)]) -> Config {
+        let search = bindings
+            .iter()
+            .map(|(chord, action)| (chord.to_string(), action.to_string()))
+            .collect();
+        Config {
+            keys: Some(KeyBindings {
+                search,
+                ..KeyBindings::default()
+            }),
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn keymap_applies_the_bindings() {
+        let config = config_with_search_keys(&[("ctrl-q", "abort"), ("f5", "execute(make)")]);
+        let keymap = config.keymap().unwrap();
+        assert_eq!(
+            keymap.action(PickerMode::Search, "ctrl-q".parse().unwrap()),
+            Some(Action::Abort)
+        );
+        assert_eq!(
+            keymap.action(PickerMode::Search, "f5".parse().unwrap()),
+            Some(Action::Execute("make".to_string()))
+        );
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn keymap_reports_invalid_bindings() {
+        for bindings in [
+            [("ctrl-q", "abort"), ("ctrl-x", "nope")],
+            [("ctrl-q", "abort"), ("shift-1", "accept")],
+            [("ctrl-q", "abort"), ("hyper-x", "accept")],
+        ] {
+            let config = config_with_search_keys(&bindings);
+            let error = config.keymap().unwrap_err();
+            assert!(error.contains(bindings[1].0), "{error}");
+            assert_eq!(config.validate(), Err(error));
+        }
+    }
 }
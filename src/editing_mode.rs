@@ -1,131 +1,121 @@
 use crate::{
-    picker::{EventResponse, Picker},
+    action::Action,
+    picker::{EventResponse, Picker, PickerMode},
+    picker_item::PickerItem,
     selectable::SelectableItem,
 };
-use crossterm::event::{Event, KeyCode, KeyModifiers};
-use std::{char, fmt::Display};
+use crossterm::event::Event;
+use std::char;
 
 impl<T> Picker<T>
 where
-    T: Sync + Send + Display,
+    T: Sync + Send + PickerItem,
 {
     /// Handle key events when in editing mode
     pub(crate) fn editing_mode_handle_event(&mut self, event: Event) -> EventResponse {
         match event {
-            Event::Key(key) => match (key.code, key.modifiers) {
-                (KeyCode::Char(ch), KeyModifiers::NONE)
-                | (KeyCode::Char(ch), KeyModifiers::SHIFT) => {
-                    self.append_to_editing_text(ch);
-                    self.editing_index = self.editing_index.saturating_add(1);
-                    self.update_autocomplete_suggestions();
-                    EventResponse::UpdateUI
-                }
-                (KeyCode::Backspace, KeyModifiers::NONE) => {
-                    self.delete_from_editing_text();
-                    self.editing_index = self.editing_index.saturating_sub(1);
-                    self.update_autocomplete_suggestions();
-                    EventResponse::UpdateUI
-                }
-                // TODO try and find a way to actually detect this
-                //      though it may not be possible without having users modify their terminal emulator config
-                (KeyCode::Backspace, KeyModifiers::CONTROL)
-                | (KeyCode::Backspace, KeyModifiers::SHIFT) => {
-                    self.delete_word_backward_editing();
-                    self.update_autocomplete_suggestions();
-                    EventResponse::UpdateUI
-                }
-                (KeyCode::Delete, KeyModifiers::NONE) => {
-                    self.delete_forward_editing();
-                    self.update_autocomplete_suggestions();
-                    EventResponse::UpdateUI
-                }
-                (KeyCode::Delete, KeyModifiers::CONTROL)
-                | (KeyCode::Delete, KeyModifiers::SHIFT) => {
-                    self.delete_word_forward_editing();
-                    self.update_autocomplete_suggestions();
-                    EventResponse::UpdateUI
-                }
-                (KeyCode::Right, KeyModifiers::NONE) => {
-                    self.editing_index = (self.editing_index + 1).min(self.editing_text.len());
-                    EventResponse::UpdateUI
-                }
-                (KeyCode::Right, KeyModifiers::CONTROL) | (KeyCode::Right, KeyModifiers::SHIFT) => {
-                    self.jump_word_forward_editing();
-                    EventResponse::UpdateUI
-                }
-                (KeyCode::Left, KeyModifiers::NONE) => {
-                    self.editing_index = self.editing_index.saturating_sub(1);
-                    EventResponse::UpdateUI
-                }
-                (KeyCode::Left, KeyModifiers::CONTROL) | (KeyCode::Left, KeyModifiers::SHIFT) => {
-                    self.jump_word_backward_editing();
-                    EventResponse::UpdateUI
-                }
-                (KeyCode::Home, KeyModifiers::NONE)
-                | (KeyCode::Char('a'), KeyModifiers::CONTROL) => {
-                    self.editing_index = 0;
-                    EventResponse::UpdateUI
-                }
-                (KeyCode::End, KeyModifiers::NONE)
-                | (KeyCode::Char('e'), KeyModifiers::CONTROL) => {
-                    self.editing_index = self.editing_text.len();
-                    EventResponse::UpdateUI
-                }
-                (KeyCode::Char('u'), KeyModifiers::CONTROL) => {
-                    self.clear_editing_text();
-                    self.update_autocomplete_suggestions();
-                    EventResponse::UpdateUI
-                }
-                (KeyCode::Char('k'), KeyModifiers::CONTROL) => {
-                    self.delete_to_end_of_line_editing();
-                    self.update_autocomplete_suggestions();
-                    EventResponse::UpdateUI
-                }
-                (KeyCode::Enter, KeyModifiers::NONE) => {
-                    self.create_items_from_editing_mode();
-                    EventResponse::UpdateUI
-                }
-                (KeyCode::Up, KeyModifiers::NONE) => {
-                    if !self.autocomplete_suggestions.is_empty() {
-                        self.autocomplete_index = self.autocomplete_index.saturating_sub(1);
-                    }
-                    EventResponse::UpdateUI
+            Event::Key(key) => match self.keymap.action(PickerMode::Editing, key.into()) {
+                Some(action) => self.editing_mode_handle_action(action),
+                None => EventResponse::NoAction,
+            },
+
+            // ignore other event types
+            _ => EventResponse::NoAction,
+        }
+    }
+
+    /// Perform an action while editing
+    pub(crate) fn editing_mode_handle_action(&mut self, action: Action) -> EventResponse {
+        match action {
+            Action::Insert(ch) => {
+                self.append_to_editing_text(ch);
+                self.editing_index = self.editing_index.saturating_add(1);
+                self.update_autocomplete_suggestions();
+            }
+            Action::BackwardDeleteChar => {
+                self.delete_from_editing_text();
+                self.editing_index = self.editing_index.saturating_sub(1);
+                self.update_autocomplete_suggestions();
+            }
+            Action::BackwardKillWord => {
+                self.delete_word_backward_editing();
+                self.update_autocomplete_suggestions();
+            }
+            Action::DeleteChar => {
+                self.delete_forward_editing();
+                self.update_autocomplete_suggestions();
+            }
+            Action::KillWord => {
+                self.delete_word_forward_editing();
+                self.update_autocomplete_suggestions();
+            }
+            Action::ForwardChar => {
+                self.editing_index = (self.editing_index + 1).min(self.editing_text.len());
+            }
+            Action::ForwardWord => {
+                self.jump_word_forward_editing();
+            }
+            Action::BackwardChar => {
+                self.editing_index = self.editing_index.saturating_sub(1);
+            }
+            Action::BackwardWord => {
+                self.jump_word_backward_editing();
+            }
+            Action::BeginningOfLine => {
+                self.editing_index = 0;
+            }
+            Action::EndOfLine => {
+                self.editing_index = self.editing_text.len();
+            }
+            Action::ClearQuery => {
+                self.clear_editing_text();
+                self.update_autocomplete_suggestions();
+            }
+            Action::KillLine => {
+                self.delete_to_end_of_line_editing();
+                self.update_autocomplete_suggestions();
+            }
+            Action::Accept => {
+                self.create_items_from_editing_mode();
+            }
+            Action::Up => {
+                if !self.autocomplete_suggestions.is_empty() {
+                    self.autocomplete_index = self.autocomplete_index.saturating_sub(1);
                 }
-                (KeyCode::Down, KeyModifiers::NONE) => {
-                    if !self.autocomplete_suggestions.is_empty() {
-                        self.autocomplete_index = (self.autocomplete_index + 1)
-                            .min(self.autocomplete_suggestions.len().saturating_sub(1));
-                    }
-                    EventResponse::UpdateUI
+            }
+            Action::Down => {
+                if !self.autocomplete_suggestions.is_empty() {
+                    self.autocomplete_index = (self.autocomplete_index + 1)
+                        .min(self.autocomplete_suggestions.len().saturating_sub(1));
                 }
-                (KeyCode::Tab, KeyModifiers::NONE) => {
-                    if !self.autocomplete_suggestions.is_empty()
-                        && self.autocomplete_index < self.autocomplete_suggestions.len()
-                    {
-                        // Toggle the selection state of the current autocomplete item
-                        self.autocomplete_suggestions[self.autocomplete_index].toggle_selected();
-
-                        // move selection down
+            }
+            Action::Toggle | Action::ToggleDown => {
+                if !self.autocomplete_suggestions.is_empty()
+                    && self.autocomplete_index < self.autocomplete_suggestions.len()
+                {
+                    // Toggle the selection state of the current autocomplete item
+                    self.autocomplete_suggestions[self.autocomplete_index].toggle_selected();
+
+                    // move selection down
+                    if action == Action::ToggleDown {
                         self.autocomplete_index = (self.autocomplete_index + 1)
                             .min(self.autocomplete_suggestions.len().saturating_sub(1));
                     }
-                    EventResponse::UpdateUI
                 }
-                (KeyCode::Esc, KeyModifiers::NONE) => {
-                    self.exit_editing_mode();
-                    EventResponse::UpdateUI
-                }
-                (KeyCode::Char('c'), KeyModifiers::CONTROL) => EventResponse::ExitProgram,
-                (KeyCode::Char('h'), KeyModifiers::CONTROL) => {
-                    self.enter_help_mode();
-                    EventResponse::UpdateUI
-                }
-                _ => EventResponse::NoAction,
-            },
+            }
+            Action::Cancel => {
+                self.exit_editing_mode();
+            }
+            Action::Abort => return EventResponse::ExitProgram,
+            Action::Help => {
+                self.enter_help_mode();
+            }
 
-            // ignore other event types
-            _ => EventResponse::NoAction,
+            // ignore actions that don't apply while editing
+            _ => return EventResponse::NoAction,
         }
+
+        EventResponse::UpdateUI
     }
 
     pub(crate) fn append_to_editing_text(&mut self, key: char) {
@@ -158,9 +148,7 @@ where
         for suggestion in &self.autocomplete_suggestions {
             if suggestion.is_selected() {
                 let new_item = SelectableItem::new_requested_selected(suggestion.to_string());
-                injector.push(new_item, |item, columns| {
-                    columns[0] = item.to_string().into()
-                });
+                injector.push(new_item, SelectableItem::fill_columns);
                 item_count += 1;
             }
         }
@@ -168,9 +156,7 @@ where
         // Add the current editing text if it's not empty and no suggestions were selected
         if item_count < 1 && !self.editing_text.is_empty() {
             let new_item = SelectableItem::new_requested_selected(self.editing_text.clone());
-            injector.push(new_item, |item, columns| {
-                columns[0] = item.to_string().into()
-            });
+            injector.push(new_item, SelectableItem::fill_columns);
         }
 
         self.exit_editing_mode();
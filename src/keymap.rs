@@ -0,0 +1,410 @@
+use crate::{action::Action, picker::PickerMode};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::{collections::HashMap, fmt, str::FromStr};
+
+/// A key together with the modifiers held while pressing it
+///
+/// Parsed from names like `ctrl-h`, `alt-backspace`, `shift-up`, `enter` or `q`. Modifiers are
+/// `ctrl`, `alt` and `shift`, keys are single characters or one of `enter`, `esc`, `tab`, `btab`,
+/// `backspace`, `delete`, `insert`, `up`, `down`, `left`, `right`, `home`, `end`, `pgup`, `pgdn`,
+/// `space` and `f1` to `f12`. Shift can't be combined with characters, since the shifted character
+/// depends on the keyboard layout, the character it types is used instead e.g. `A` or `alt-!`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        // shift is part of the character itself and terminals report shift-tab as its own key
+        let modifiers = match code {
+            KeyCode::Char(_) | KeyCode::BackTab => modifiers.difference(KeyModifiers::SHIFT),
+            _ => modifiers,
+        };
+        Self { code, modifiers }
+    }
+}
+
+impl From<KeyEvent> for KeyChord {
+    fn from(key: KeyEvent) -> Self {
+        Self::new(key.code, key.modifiers)
+    }
+}
+
+impl FromStr for KeyChord {
+    type Err = String;
+
+    fn from_str(chord: &str) -> Result<Self, Self::Err> {
+        let chord = chord.trim();
+        let (modifier_names, key) = if chord.len() > 1 && chord.ends_with("--") {
+            // the minus key itself, e.g. `alt--`
+            (&chord[..chord.len() - 2], "-")
+        } else {
+            match chord.rsplit_once('-') {
+                Some((modifier_names, key)) if !key.is_empty() => (modifier_names, key),
+                _ => ("", chord),
+            }
+        };
+
+        let mut modifiers = KeyModifiers::NONE;
+        for name in modifier_names.split('-').filter(|name| !name.is_empty()) {
+            modifiers |= match name.to_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" | "meta" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(format!("unknown modifier {name:?} in key {chord:?}")),
+            };
+        }
+
+        let mut characters = key.chars();
+        let code = match (characters.next(), characters.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match key.to_lowercase().as_str() {
+                "enter" | "return" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "tab" if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+                "tab" => KeyCode::Tab,
+                "btab" => KeyCode::BackTab,
+                "backspace" | "bspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" => KeyCode::Insert,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pgup" | "pageup" => KeyCode::PageUp,
+                "pgdn" | "pagedown" => KeyCode::PageDown,
+                "space" => KeyCode::Char(' '),
+                name => match name.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                    Some(n @ 1..=12) => KeyCode::F(n),
+                    _ => return Err(format!("unknown key {key:?} in {chord:?}")),
+                },
+            },
+        };
+
+        if matches!(code, KeyCode::Char(_)) && modifiers.contains(KeyModifiers::SHIFT) {
+            return Err(format!(
+                "shift can't be combined with {key:?} in {chord:?}, use the character it types instead"
+            ));
+        }
+
+        Ok(Self::new(code, modifiers))
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "Ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "Alt+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "Shift+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) => write!(f, "{c}"),
+            KeyCode::Enter => write!(f, "Enter"),
+            KeyCode::Esc => write!(f, "Esc"),
+            KeyCode::Tab => write!(f, "Tab"),
+            KeyCode::BackTab => write!(f, "Shift+Tab"),
+            KeyCode::Backspace => write!(f, "Backspace"),
+            KeyCode::Delete => write!(f, "Delete"),
+            KeyCode::Insert => write!(f, "Insert"),
+            KeyCode::Up => write!(f, "↑"),
+            KeyCode::Down => write!(f, "↓"),
+            KeyCode::Left => write!(f, "←"),
+            KeyCode::Right => write!(f, "→"),
+            KeyCode::Home => write!(f, "Home"),
+            KeyCode::End => write!(f, "End"),
+            KeyCode::PageUp => write!(f, "PgUp"),
+            KeyCode::PageDown => write!(f, "PgDn"),
+            KeyCode::F(n) => write!(f, "F{n}"),
+            code => write!(f, "{code:?}"),
+        }
+    }
+}
+
+/// Maps key chords to actions, separately for each [`PickerMode`]
+///
+/// The default keymap contains the built in bindings, [`Keymap::bind`] adds to or replaces them.
+/// Characters typed without a binding are inserted into the query or editing text.
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    search: HashMap<KeyChord, Action>,
+    editing: HashMap<KeyChord, Action>,
+    help: HashMap<KeyChord, Action>,
+}
+
+impl Keymap {
+    /// Create a keymap without any bindings
+    pub fn empty() -> Self {
+        Self {
+            search: HashMap::new(),
+            editing: HashMap::new(),
+            help: HashMap::new(),
+        }
+    }
+
+    /// Bind the key chord to an action in one mode, replacing any existing binding
+    pub fn bind(&mut self, mode: PickerMode, chord: KeyChord, action: Action) {
+        self.bindings_mut(mode).insert(chord, action);
+    }
+
+    /// Bind a key chord to an action by their names, e.g. `ctrl-h` and `backward-delete-char`
+    pub fn bind_names(
+        &mut self,
+        mode: PickerMode,
+        chord: &str,
+        action: &str,
+    ) -> Result<(), String> {
+        self.bind(mode, chord.parse()?, action.parse()?);
+        Ok(())
+    }
+
+    /// Returns the action for a key chord, unbound characters are inserted
+    pub fn action(&self, mode: PickerMode, chord: KeyChord) -> Option<Action> {
+        match self.bindings(mode).get(&chord) {
+            Some(action) => Some(action.clone()),
+            None => match (chord.code, chord.modifiers) {
+                (KeyCode::Char(c), KeyModifiers::NONE) => Some(Action::Insert(c)),
+                _ => None,
+            },
+        }
+    }
+
+    /// Returns all bindings of one mode
+    pub fn bindings(&self, mode: PickerMode) -> &HashMap<KeyChord, Action> {
+        match mode {
+            PickerMode::Search => &self.search,
+            PickerMode::Editing => &self.editing,
+            PickerMode::Help => &self.help,
+        }
+    }
+
+    fn bindings_mut(&mut self, mode: PickerMode) -> &mut HashMap<KeyChord, Action> {
+        match mode {
+            PickerMode::Search => &mut self.search,
+            PickerMode::Editing => &mut self.editing,
+            PickerMode::Help => &mut self.help,
+        }
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        use Action as A;
+        use KeyCode::*;
+
+        let none = KeyModifiers::NONE;
+        let ctrl = KeyModifiers::CONTROL;
+        let alt = KeyModifiers::ALT;
+        let shift = KeyModifiers::SHIFT;
+
+        let mut keymap = Keymap::empty();
+        let search = [
+            (Backspace, none, A::BackwardDeleteChar),
+            // TODO find out if it's a local keybinding that's preventing `Ctrl + Backspace` from working or if it's actually a bug
+            (Backspace, ctrl, A::BackwardKillWord),
+            (Backspace, alt, A::BackwardKillWord),
+            (Right, none, A::ForwardChar),
+            (Right, ctrl, A::ForwardWord),
+            (Right, alt, A::ForwardWord),
+            (Left, none, A::BackwardChar),
+            (Left, ctrl, A::BackwardWord),
+            (Left, alt, A::BackwardWord),
+            (Delete, ctrl, A::KillWord),
+            (Delete, alt, A::KillWord),
+            (Esc, none, A::Cancel),
+            (Char('c'), ctrl, A::Abort),
+            (Char('u'), ctrl, A::ClearQuery),
+            (Char('a'), ctrl, A::BeginningOfLine),
+            (Char('e'), ctrl, A::EndOfLine),
+            (Char('k'), ctrl, A::KillLine),
+            (Enter, none, A::Accept),
+            (Down, none, A::Down),
+            (PageDown, none, A::PageDown),
+            (End, none, A::Last),
+            (Up, none, A::Up),
+            (PageUp, none, A::PageUp),
+            (Home, none, A::First),
+            (Up, shift, A::PreviewUp),
+            (Down, shift, A::PreviewDown),
+            (PageUp, shift, A::PreviewPageUp),
+            (PageDown, shift, A::PreviewPageDown),
+            (Tab, none, A::ToggleDown),
+            (Char('d'), ctrl, A::EditItem),
+            (Char('n'), ctrl, A::NewItem),
+            (Char('h'), ctrl, A::Help),
+            (Char('r'), ctrl, A::Reload),
+            (Char('i'), alt, A::ToggleInteractive),
+            (Char('p'), alt, A::TogglePreview),
+            (Char('l'), alt, A::CyclePreview),
+        ];
+        let editing = [
+            (Backspace, none, A::BackwardDeleteChar),
+            // TODO try and find a way to actually detect this
+            //      though it may not be possible without having users modify their terminal emulator config
+            (Backspace, ctrl, A::BackwardKillWord),
+            (Backspace, shift, A::BackwardKillWord),
+            (Delete, none, A::DeleteChar),
+            (Delete, ctrl, A::KillWord),
+            (Delete, shift, A::KillWord),
+            (Right, none, A::ForwardChar),
+            (Right, ctrl, A::ForwardWord),
+            (Right, shift, A::ForwardWord),
+            (Left, none, A::BackwardChar),
+            (Left, ctrl, A::BackwardWord),
+            (Left, shift, A::BackwardWord),
+            (Home, none, A::BeginningOfLine),
+            (Char('a'), ctrl, A::BeginningOfLine),
+            (End, none, A::EndOfLine),
+            (Char('e'), ctrl, A::EndOfLine),
+            (Char('u'), ctrl, A::ClearQuery),
+            (Char('k'), ctrl, A::KillLine),
+            (Enter, none, A::Accept),
+            (Up, none, A::Up),
+            (Down, none, A::Down),
+            (Tab, none, A::ToggleDown),
+            (Esc, none, A::Cancel),
+            (Char('c'), ctrl, A::Abort),
+            (Char('h'), ctrl, A::Help),
+        ];
+        let help = [
+            (Esc, none, A::Cancel),
+            (Char('q'), none, A::Cancel),
+            (Char('h'), ctrl, A::Help),
+            (Char('c'), ctrl, A::Abort),
+            (Up, none, A::Up),
+            (Char('k'), none, A::Up),
+            (Down, none, A::Down),
+            (Char('j'), none, A::Down),
+            (PageUp, none, A::PageUp),
+            (PageDown, none, A::PageDown),
+            (Home, none, A::First),
+            (End, none, A::Last),
+        ];
+
+        for (mode, bindings) in [
+            (PickerMode::Search, &search[..]),
+            (PickerMode::Editing, &editing[..]),
+            (PickerMode::Help, &help[..]),
+        ] {
+            for (code, modifiers, action) in bindings {
+                keymap.bind(mode, KeyChord::new(*code, *modifiers), action.clone());
+            }
+        }
+        keymap
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chord(code: KeyCode, modifiers: KeyModifiers) -> Result<KeyChord, String> {
+        Ok(KeyChord::new(code, modifiers))
+    }
+
+    #[test]
+    fn parses_characters_with_modifiers() {
+        let ctrl = KeyModifiers::CONTROL;
+        let alt = KeyModifiers::ALT;
+        assert_eq!("q".parse(), chord(KeyCode::Char('q'), KeyModifiers::NONE));
+        assert_eq!("Q".parse(), chord(KeyCode::Char('Q'), KeyModifiers::NONE));
+        assert_eq!("ctrl-h".parse(), chord(KeyCode::Char('h'), ctrl));
+        assert_eq!("Control-h".parse(), chord(KeyCode::Char('h'), ctrl));
+        assert_eq!("meta-x".parse(), chord(KeyCode::Char('x'), alt));
+        assert_eq!("ctrl-alt-x".parse(), chord(KeyCode::Char('x'), ctrl | alt));
+        assert_eq!(" alt-x ".parse(), chord(KeyCode::Char('x'), alt));
+    }
+
+    #[test]
+    fn parses_the_minus_key() {
+        let none = KeyModifiers::NONE;
+        assert_eq!("-".parse(), chord(KeyCode::Char('-'), none));
+        assert_eq!(
+            "alt--".parse(),
+            chord(KeyCode::Char('-'), KeyModifiers::ALT)
+        );
+    }
+
+    #[test]
+    fn parses_named_keys() {
+        let none = KeyModifiers::NONE;
+        let shift = KeyModifiers::SHIFT;
+        assert_eq!("enter".parse(), chord(KeyCode::Enter, none));
+        assert_eq!("ESC".parse(), chord(KeyCode::Esc, none));
+        assert_eq!("space".parse(), chord(KeyCode::Char(' '), none));
+        assert_eq!("shift-up".parse(), chord(KeyCode::Up, shift));
+        assert_eq!("shift-pgdn".parse(), chord(KeyCode::PageDown, shift));
+        assert_eq!(
+            "alt-backspace".parse(),
+            chord(KeyCode::Backspace, KeyModifiers::ALT)
+        );
+        assert_eq!("f1".parse(), chord(KeyCode::F(1), none));
+        assert_eq!("f12".parse(), chord(KeyCode::F(12), none));
+
+        // shift-tab is reported as its own key
+        assert_eq!("shift-tab".parse(), chord(KeyCode::BackTab, none));
+        assert_eq!("btab".parse(), chord(KeyCode::BackTab, none));
+    }
+
+    #[test]
+    fn rejects_unknown_keys_and_modifiers() {
+        for name in ["", "f0", "f13", "enterr", "hyper-x", "ctrl-", "ctrl-xy"] {
+            assert!(name.parse::<KeyChord>().is_err(), "{name:?} was accepted");
+        }
+    }
+
+    #[test]
+    fn rejects_shift_with_characters() {
+        for name in [
+            "shift-a",
+            "shift-1",
+            "shift-space",
+            "ctrl-shift-x",
+            "shift--",
+        ] {
+            assert!(name.parse::<KeyChord>().is_err(), "{name:?} was accepted");
+        }
+    }
+
+    #[test]
+    fn displays_chords() {
+        let display = |name: &str| name.parse::<KeyChord>().unwrap().to_string();
+        assert_eq!(display("ctrl-alt-x"), "Ctrl+Alt+x");
+        assert_eq!(display("shift-up"), "Shift+↑");
+        assert_eq!(display("btab"), "Shift+Tab");
+        assert_eq!(display("space"), "Space");
+    }
+
+    #[test]
+    fn unbound_characters_are_inserted() {
+        let keymap = Keymap::empty();
+        let search = PickerMode::Search;
+        assert_eq!(
+            keymap.action(search, "x".parse().unwrap()),
+            Some(Action::Insert('x'))
+        );
+        assert_eq!(keymap.action(search, "ctrl-x".parse().unwrap()), None);
+    }
+
+    #[test]
+    fn bindings_replace_defaults() {
+        let mut keymap = Keymap::default();
+        let search = PickerMode::Search;
+        keymap.bind_names(search, "ctrl-r", "ignore").unwrap();
+        assert_eq!(
+            keymap.action(search, "ctrl-r".parse().unwrap()),
+            Some(Action::Ignore)
+        );
+        assert!(keymap.bind_names(search, "ctrl-r", "nope").is_err());
+        assert!(keymap.bind_names(search, "shift-r", "accept").is_err());
+    }
+}
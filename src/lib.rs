@@ -1,8 +1,19 @@
+pub mod action;
+pub mod byte_line;
+pub mod command;
 pub mod config;
 pub mod editing_mode;
+pub mod keymap;
 pub mod picker;
+pub mod picker_builder;
+pub mod picker_item;
+pub mod placeholders;
+mod preview;
+pub mod preview_layout;
+pub mod query;
 pub mod requested_items;
 pub mod search_mode;
 pub mod selectable;
 pub mod selected_items;
+pub mod source;
 mod ui;
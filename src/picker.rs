@@ -1,18 +1,47 @@
+use crate::action::Action;
+use crate::command::{ExecutionMode, run_in_foreground};
+use crate::keymap::{KeyChord, Keymap};
+use crate::picker_builder::PickerBuilder;
+use crate::picker_item::{Column, PickerItem};
+use crate::placeholders::Delimiter;
+use crate::preview::PreviewRunner;
+use crate::preview_layout::PreviewLayout;
 use crate::requested_items::RequestedItems;
-use crate::{config::Config, selectable::SelectableItem, selected_items::SelectedItems, ui::ui};
+use crate::search_mode::PreviewLineCount;
+use crate::source::{SourceContext, SourceFn};
+use crate::{
+    config::Config,
+    selectable::SelectableItem,
+    selected_items::SelectedItems,
+    ui::{help_lines, ui},
+};
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyModifiers},
+    cursor::Show,
+    event::{self, DisableMouseCapture, EnableMouseCapture, Event},
     execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
-use nucleo::{Config as NucleoConfig, Injector, Nucleo, Snapshot};
+use nucleo::pattern::{CaseMatching, Normalization};
+use nucleo::{Config as NucleoConfig, Injector, Matcher, Nucleo, Snapshot};
+use ratatui::layout::Rect;
 use ratatui::prelude::Backend;
 use ratatui::{Terminal, prelude::CrosstermBackend};
 use std::time::Instant;
-use std::{error, fmt::Display, io, sync::Arc, thread::JoinHandle, time::Duration};
+use std::{
+    collections::HashMap,
+    error, fs, io,
+    path::PathBuf,
+    process::Command,
+    sync::{Arc, atomic::AtomicBool, atomic::Ordering},
+    thread::JoinHandle,
+    time::Duration,
+};
 
 pub type AppResult<T> = std::result::Result<T, Box<dyn error::Error>>;
 
+/// Generates autocomplete suggestions from the current editing text
+pub type AutocompleteFn = Box<dyn Fn(&str) -> RequestedItems<String> + Send + Sync>;
+
 // This is the number of milliseconds between frames, target 60 fps, 1000 / 60 = 16ms (positive integer division floors the result)
 // Yes, u64 is overkill, but it's what Duration::from_millis() wants
 const FRAME_DELAY: u64 = 1000 / 60;
@@ -29,6 +58,41 @@ pub(crate) enum EventResponse {
     UpdateUI,
     ExitProgram,
     ReturnSelectedItems,
+    /// Suspend the picker and run the command in the terminal
+    Execute(Command),
+    /// Exit the picker and replace it with the command
+    Become(Command),
+}
+
+/// What happens to the picker after an action was dispatched
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActionOutcome {
+    /// The picker keeps running
+    Continue,
+
+    /// The selection was accepted, see [`Picker::selected_items`]
+    Accept,
+
+    /// The picker was exited without a selection
+    Abort,
+}
+
+/// How a run of the picker ended
+#[derive(Debug)]
+pub enum PickerOutcome<'a, T> {
+    /// The selection was accepted, this is the current item if nothing was selected and empty if
+    /// nothing matched
+    Accepted(SelectedItems<'a, T>),
+
+    /// The picker was exited without accepting anything
+    Aborted,
+
+    /// Nothing matched once all items were loaded and [`Picker::exit_zero`] is set
+    NoMatch,
+
+    /// A become action exited the picker, the caller is expected to run the command in its place,
+    /// e.g. with [`replace_process`](crate::command::replace_process)
+    Become(Command),
 }
 
 // TODO convert static to a proper lifetime
@@ -37,6 +101,10 @@ where
     T: Sync + Send + 'static,
 {
     pub matcher: Nucleo<SelectableItem<T>>,
+    pub columns: Vec<Column>,
+    pub default_column: usize,
+    pub(crate) column_patterns: Vec<String>,
+    pub(crate) highlight_matcher: Matcher,
     pub first_visible_item_index: u32,
     pub current_index: u32,
     pub height: u16,
@@ -46,34 +114,112 @@ where
     pub editing_text: String,
     pub editing_index: usize,
     pub join_handles: Vec<JoinHandle<()>>,
+    pub(crate) source: Option<SourceFn<T>>,
+    /// Cancellation flag handed to the source by the latest reload
+    pub(crate) source_cancelled: Arc<AtomicBool>,
+    /// Text of the item the cursor was on before a reload, until that item is found again
+    pub(crate) reload_cursor: Option<String>,
+    /// Number of loaded items whose text was decoded lossily, shown in the status area
+    pub lossy_item_count: usize,
+    /// Number of items that were already checked for lossy text
+    pub(crate) lossy_checked_items: u32,
+    /// Whether the query is passed to the source instead of filtering the items
+    pub interactive: bool,
+    /// Query of the other side of [`Picker::toggle_interactive`]
+    pub(crate) stashed_query: String,
+    /// When the query changed in interactive mode, the source is reloaded once this time passed
+    pub(crate) pending_reload: Option<Instant>,
     pub config: Config,
     pub preview_command: Option<String>,
+    pub execution_mode: ExecutionMode,
+    pub delimiter: Delimiter,
+    pub field_names: Vec<String>,
+    /// Temporary files created for `{+f}` placeholders by the hash of their contents, removed when
+    /// the picker is dropped
+    pub(crate) selection_files: HashMap<u64, PathBuf>,
+    /// Command requested by a become action, see [`Picker::take_become_command`]
+    pub(crate) become_command: Option<Command>,
     pub preview_output: String,
+    /// Wrapped line count of the preview output, only counted again when the output or pane changes
+    pub(crate) preview_line_count: Option<PreviewLineCount>,
+    pub(crate) preview: PreviewRunner,
+    pub(crate) items_area: Rect,
+    pub(crate) preview_area: Rect,
+    pub preview_scroll_offset: u16,
+    pub preview_layout: PreviewLayout,
+    pub keymap: Keymap,
+    /// Keys that accept the selection and are reported with it
+    pub expect_keys: Vec<KeyChord>,
+    /// The expected key that accepted the selection, if any
+    pub(crate) accepted_key: Option<KeyChord>,
+    pub(crate) preview_item_text: String,
     pub keep_colors: bool,
+    /// Return the only match without showing the picker, once all items are loaded
+    pub select_one: bool,
+    /// Return [`PickerOutcome::NoMatch`] without showing the picker if nothing matches once all
+    /// items are loaded
+    pub exit_zero: bool,
     pub editable: bool,
-    pub autocomplete: Option<Box<dyn Fn(&str) -> RequestedItems<String> + Send + Sync>>,
+    pub case_matching: CaseMatching,
+    pub normalization: Normalization,
+    pub autocomplete: Option<AutocompleteFn>,
     pub autocomplete_suggestions: RequestedItems<String>,
     pub autocomplete_index: usize,
     pub help_scroll_offset: u16,
 }
 
-impl<T: Sync + Send + Display> Default for Picker<T> {
+impl<T: Sync + Send + PickerItem> Default for Picker<T> {
     fn default() -> Self {
         Self::new(true)
     }
 }
 
+impl<T> Drop for Picker<T>
+where
+    T: Sync + Send + 'static,
+{
+    fn drop(&mut self) {
+        for path in self.selection_files.values() {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
 // TODO maybe expose the Nucleo update callback
 impl<T> Picker<T>
 where
-    T: Sync + Send + Display,
+    T: Sync + Send + PickerItem,
 {
+    /// Create a picker with the on-disk config, invalid settings fall back to their defaults
+    ///
+    /// Use [`Picker::builder`] to have mistakes in the config reported instead.
     pub fn new(editable: bool) -> Self {
-        let config = Config::load().unwrap_or_default();
-        let matcher = Nucleo::new(NucleoConfig::DEFAULT, Arc::new(|| {}), None, 1);
+        Self::with_config(
+            Config::load().unwrap_or_default(),
+            vec![Column::default()],
+            editable,
+        )
+    }
+
+    /// Returns a builder for configuring a picker before creating it
+    pub fn builder() -> PickerBuilder<T> {
+        PickerBuilder::new()
+    }
+
+    pub(crate) fn with_config(config: Config, columns: Vec<Column>, editable: bool) -> Self {
+        let matcher = Nucleo::new(
+            NucleoConfig::DEFAULT,
+            Arc::new(|| {}),
+            None,
+            columns.len() as u32,
+        );
         let preview_command = config.preview_command().cloned();
         Picker {
             matcher,
+            column_patterns: vec![String::new(); columns.len()],
+            columns,
+            default_column: 0,
+            highlight_matcher: Matcher::new(NucleoConfig::DEFAULT),
             first_visible_item_index: 0,
             current_index: 0,
             height: config.height().unwrap_or(0),
@@ -83,15 +229,43 @@ where
             editing_text: String::new(),
             editing_index: 0,
             join_handles: Vec::new(),
-            config,
+            source: None,
+            source_cancelled: Arc::new(AtomicBool::new(false)),
+            reload_cursor: None,
+            lossy_item_count: 0,
+            lossy_checked_items: 0,
+            interactive: false,
+            stashed_query: String::new(),
+            pending_reload: None,
             preview_command,
+            execution_mode: config.execution_mode(),
+            delimiter: config.delimiter(),
+            field_names: config.field_names.clone().unwrap_or_default(),
+            selection_files: HashMap::new(),
+            become_command: None,
             preview_output: String::new(),
+            preview_line_count: None,
+            preview: PreviewRunner::new(config.preview_cache_size(), config.preview_cache_ttl()),
+            items_area: Rect::default(),
+            preview_area: Rect::default(),
+            preview_scroll_offset: 0,
+            preview_layout: config.preview_layout(),
+            // new() ignores an invalid config, the builder validates it first
+            keymap: config.keymap().unwrap_or_default(),
+            expect_keys: Vec::new(),
+            accepted_key: None,
+            preview_item_text: String::new(),
             keep_colors: false,
+            select_one: false,
+            exit_zero: false,
             editable,
+            case_matching: config.case_matching(),
+            normalization: config.normalization(),
             autocomplete: None,
             autocomplete_suggestions: RequestedItems::default(),
             autocomplete_index: 0,
             help_scroll_offset: 0,
+            config,
         }
     }
 
@@ -114,6 +288,102 @@ where
         self.join_handles.push(handle);
     }
 
+    /// Load the items from a source, replacing any items that were injected before
+    ///
+    /// The source runs on its own thread and is run again by [`Picker::reload`].
+    pub fn set_source<F>(&mut self, source: F)
+    where
+        F: Fn(&SourceContext, &Injector<SelectableItem<T>>) + Send + Sync + 'static,
+    {
+        self.source = Some(Arc::new(source));
+        self.reload_cursor = None;
+        self.reload();
+    }
+
+    /// Clear all items and load them from the source again, keeping the query
+    ///
+    /// The cursor moves back to the item it was on once that item is loaded again, selections are
+    /// cleared. The source is given the interactive query, which is kept aside while filtering the
+    /// items instead. Does nothing if the picker has no source.
+    pub fn reload(&mut self) {
+        self.reload_source(true);
+    }
+
+    /// Reload the source, moving the cursor back to the current item if `keep_cursor` is set or to
+    /// the first item otherwise
+    fn reload_source(&mut self, keep_cursor: bool) {
+        let Some(source) = self.source.clone() else {
+            return;
+        };
+
+        let current_item = self.current_item_text();
+        self.reload_cursor = None;
+        if !keep_cursor {
+            self.set_current_index(0, Some(false));
+        } else if !current_item.is_empty() {
+            self.reload_cursor = Some(current_item);
+        }
+
+        // stop a source that is still loading the previous items
+        self.source_cancelled.store(true, Ordering::Relaxed);
+        self.source_cancelled = Arc::new(AtomicBool::new(false));
+        let query = if self.interactive {
+            &self.query
+        } else {
+            &self.stashed_query
+        };
+        let context = SourceContext {
+            query: query.clone(),
+            cancelled: self.source_cancelled.clone(),
+        };
+
+        self.pending_reload = None;
+        self.lossy_item_count = 0;
+        self.lossy_checked_items = 0;
+        self.matcher.restart(true);
+        self.inject_items_threaded(move |injector| source(&context, injector));
+    }
+
+    /// Count the lossy items among the items that were loaded since the last call
+    fn count_lossy_items(&mut self) {
+        let snapshot = self.matcher.snapshot();
+        let item_count = snapshot.item_count();
+        while self.lossy_checked_items < item_count {
+            // items that are still being pushed are checked on a later tick
+            let Some(item) = snapshot.get_item(self.lossy_checked_items) else {
+                break;
+            };
+            if item.data.is_lossy() {
+                self.lossy_item_count += 1;
+            }
+            self.lossy_checked_items += 1;
+        }
+    }
+
+    /// Move the cursor back to the item it was on before a reload, once it has been loaded
+    fn restore_reload_cursor(&mut self, status: nucleo::Status) {
+        let Some(item_text) = self.reload_cursor.clone() else {
+            return;
+        };
+
+        let index = if status.changed {
+            self.snapshot()
+                .matched_items(..)
+                .position(|item| item.data.text() == item_text)
+        } else {
+            None
+        };
+
+        if let Some(index) = index {
+            self.reload_cursor = None;
+            self.set_current_index(index as i64, Some(false));
+        } else if !status.running && self.join_handles.is_empty() {
+            // the item is gone, stay at the same position as far as possible
+            self.reload_cursor = None;
+            self.set_current_index(self.current_index.into(), Some(false));
+        }
+    }
+
     pub fn join_finished_threads(&mut self) -> usize {
         let mut remaining_handles = Vec::new();
 
@@ -156,7 +426,18 @@ where
     pub fn tick(&mut self, timeout: u64) -> nucleo::Status {
         // TODO ensure that this is the correct place to call the thread join
         let _running_indexers = self.join_finished_threads();
-        self.matcher.tick(timeout)
+        if self
+            .pending_reload
+            .is_some_and(|reload_at| Instant::now() >= reload_at)
+        {
+            // the query changed, so the previous items have nothing to do with the new ones
+            self.pending_reload = None;
+            self.reload_source(false);
+        }
+        let status = self.matcher.tick(timeout);
+        self.count_lossy_items();
+        self.restore_reload_cursor(status);
+        status
     }
 
     pub fn snapshot(&self) -> &Snapshot<SelectableItem<T>> {
@@ -189,16 +470,15 @@ where
     }
 
     fn max_help_scroll_offset(&self) -> u16 {
-        // Help content has approximately 38 lines (counted from render_help_screen)
-        const HELP_CONTENT_LINES: u16 = 38;
+        let help_content_lines = help_lines(&self.keymap).len() as u16;
 
         // Account for borders (2 lines) in the help screen
         let available_height = self.height.saturating_sub(2);
 
         // Ensure we can't scroll so far that the screen becomes blank
         // Keep at least one screenful visible
-        if HELP_CONTENT_LINES > available_height {
-            HELP_CONTENT_LINES.saturating_sub(available_height)
+        if help_content_lines > available_height {
+            help_content_lines.saturating_sub(available_height)
         } else {
             0
         }
@@ -220,52 +500,9 @@ where
 
     pub(crate) fn help_mode_handle_event(&mut self, event: Event) -> EventResponse {
         match event {
-            Event::Key(key) => match key.code {
-                KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('h') => {
-                    if key.modifiers.contains(KeyModifiers::CONTROL)
-                        && key.code == KeyCode::Char('h')
-                    {
-                        self.exit_help_mode();
-                        EventResponse::UpdateUI
-                    } else if key.code == KeyCode::Esc || key.code == KeyCode::Char('q') {
-                        self.exit_help_mode();
-                        EventResponse::UpdateUI
-                    } else {
-                        EventResponse::NoAction
-                    }
-                }
-                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                    EventResponse::ExitProgram
-                }
-                KeyCode::Up | KeyCode::Char('k') => {
-                    self.help_scroll_offset = self.help_scroll_offset.saturating_sub(1);
-                    EventResponse::UpdateUI
-                }
-                KeyCode::Down | KeyCode::Char('j') => {
-                    let max_offset = self.max_help_scroll_offset();
-                    self.help_scroll_offset =
-                        (self.help_scroll_offset.saturating_add(1)).min(max_offset);
-                    EventResponse::UpdateUI
-                }
-                KeyCode::PageUp => {
-                    self.help_scroll_offset = self.help_scroll_offset.saturating_sub(10);
-                    EventResponse::UpdateUI
-                }
-                KeyCode::PageDown => {
-                    let max_offset = self.max_help_scroll_offset();
-                    self.help_scroll_offset =
-                        (self.help_scroll_offset.saturating_add(10)).min(max_offset);
-                    EventResponse::UpdateUI
-                }
-                KeyCode::Home => {
-                    self.help_scroll_offset = 0;
-                    EventResponse::UpdateUI
-                }
-                KeyCode::End => {
-                    self.help_scroll_offset = self.max_help_scroll_offset();
-                    EventResponse::UpdateUI
-                }
-                _ => EventResponse::NoAction,
+            Event::Key(key) => match self.keymap.action(PickerMode::Help, key.into()) {
+                Some(action) => self.help_mode_handle_action(action),
+                None => EventResponse::NoAction,
             },
             Event::Mouse(mouse) => match mouse.kind {
                 crossterm::event::MouseEventKind::ScrollUp => {
@@ -284,33 +521,111 @@ where
         }
     }
 
-    pub fn run(&mut self) -> AppResult<SelectedItems<'_, T>> {
+    /// Perform an action while the help screen is shown
+    pub(crate) fn help_mode_handle_action(&mut self, action: Action) -> EventResponse {
+        match action {
+            Action::Cancel | Action::Help => {
+                self.exit_help_mode();
+            }
+            Action::Abort => return EventResponse::ExitProgram,
+            Action::Up => {
+                self.help_scroll_offset = self.help_scroll_offset.saturating_sub(1);
+            }
+            Action::Down => {
+                let max_offset = self.max_help_scroll_offset();
+                self.help_scroll_offset =
+                    (self.help_scroll_offset.saturating_add(1)).min(max_offset);
+            }
+            Action::PageUp => {
+                self.help_scroll_offset = self.help_scroll_offset.saturating_sub(10);
+            }
+            Action::PageDown => {
+                let max_offset = self.max_help_scroll_offset();
+                self.help_scroll_offset =
+                    (self.help_scroll_offset.saturating_add(10)).min(max_offset);
+            }
+            Action::First => {
+                self.help_scroll_offset = 0;
+            }
+            Action::Last => {
+                self.help_scroll_offset = self.max_help_scroll_offset();
+            }
+
+            // ignore actions that don't apply to the help screen
+            _ => return EventResponse::NoAction,
+        }
+
+        EventResponse::UpdateUI
+    }
+
+    pub fn run(&mut self) -> AppResult<PickerOutcome<'_, T>> {
+        // the interface is only shown once it's clear that there is something to choose from
+        if self.select_one || self.exit_zero {
+            self.wait_for_matches();
+            match self.snapshot().matched_item_count() {
+                0 if self.exit_zero => return Ok(PickerOutcome::NoMatch),
+                1 if self.select_one => return Ok(PickerOutcome::Accepted(self.selected_items())),
+                _ => {}
+            }
+        }
+
         // Setup terminal
-        enable_raw_mode()?;
+        let mouse_enabled = self.config.mouse_enabled();
+        enter_terminal(mouse_enabled)?;
         // TODO should we allow the caller to pass any arbitrary stream?
-        let mut stream = io::stderr();
-        execute!(stream, EnterAlternateScreen, EnableMouseCapture)?;
-        let backend = CrosstermBackend::new(stream);
+        let backend = CrosstermBackend::new(io::stderr());
         let mut terminal = Terminal::new(backend)?;
 
         let result = self.run_loop(&mut terminal);
 
         // Restore terminal
-        disable_raw_mode()?;
-        execute!(
-            terminal.backend_mut(),
-            LeaveAlternateScreen,
-            DisableMouseCapture
-        )?;
-        terminal.show_cursor()?;
+        leave_terminal(mouse_enabled)?;
 
         result
     }
 
+    /// Match the items against a query without a terminal and return the matches in score order
+    ///
+    /// Waits for all injector threads and for the matcher to finish first. At most `limit` items
+    /// are returned.
+    pub fn filter(&mut self, query: &str, limit: Option<usize>) -> SelectedItems<'_, T> {
+        self.set_query(query);
+
+        // there is nobody typing, so an interactive source runs right away
+        if self.pending_reload.take().is_some() {
+            self.reload_source(false);
+        }
+
+        self.wait_for_matches();
+
+        let snapshot = self.snapshot();
+        let count = match limit {
+            Some(limit) => snapshot.matched_item_count().min(limit as u32),
+            None => snapshot.matched_item_count(),
+        };
+        SelectedItems::from_refs(
+            snapshot
+                .matched_items(..count)
+                .map(|item| item.data)
+                .collect(),
+        )
+        .accepted_with(self.query.clone(), None)
+    }
+
+    /// Block until every injector thread has finished and all items have been matched
+    fn wait_for_matches(&mut self) {
+        loop {
+            let status = self.tick(FRAME_DELAY);
+            if !status.running && self.running_threads() == 0 {
+                break;
+            }
+        }
+    }
+
     pub(crate) fn run_loop<B: ratatui::backend::Backend>(
         &mut self,
         terminal: &mut Terminal<B>,
-    ) -> AppResult<SelectedItems<'_, T>>
+    ) -> AppResult<PickerOutcome<'_, T>>
     where
         <B as Backend>::Error: 'static,
     {
@@ -328,7 +643,13 @@ where
             //   2. the matcher's status has changed
             //   3. injectors are still running and adding items
             if redraw_requested {
+                let previous_preview_area = self.preview_area;
                 terminal.draw(|f| ui(f, self))?;
+
+                // re-run the preview when the pane changes size since commands may depend on it
+                if self.preview_area != previous_preview_area {
+                    self.update_preview();
+                }
             }
 
             // toggling this back to the default, it will be switched back to true below on appropriate conditions
@@ -347,6 +668,11 @@ where
                 redraw_requested = true;
             }
 
+            // show the output of preview commands as soon as they finish
+            if self.poll_preview() {
+                redraw_requested = true;
+            }
+
             // check how long it took us for the tick command to complete and render the preview
             let frame_draw_duration = frame_draw_start.elapsed().as_millis() as u64;
             // clamp the value between 1ms and FRAME_DELAY, setting the poll timeout to the remainder of the total delay
@@ -369,8 +695,25 @@ where
                     match self.handle_event_by_mode(event) {
                         EventResponse::NoAction => {}
                         EventResponse::UpdateUI => redraw_requested = true,
-                        EventResponse::ExitProgram => return Ok(SelectedItems::from_refs(vec![])),
-                        EventResponse::ReturnSelectedItems => return Ok(self.selected_items()),
+                        EventResponse::ExitProgram => return Ok(PickerOutcome::Aborted),
+                        EventResponse::ReturnSelectedItems => {
+                            return Ok(PickerOutcome::Accepted(self.selected_items()));
+                        }
+                        EventResponse::Execute(command) => {
+                            leave_terminal(self.config.mouse_enabled())?;
+                            // a command that fails to start is treated like any other failing command
+                            let _ = run_in_foreground(command);
+                            enter_terminal(self.config.mouse_enabled())?;
+
+                            // the command may have changed what the preview shows
+                            terminal.clear()?;
+                            self.preview.refresh();
+                            self.update_preview();
+                            redraw_requested = true;
+                        }
+                        EventResponse::Become(command) => {
+                            return Ok(PickerOutcome::Become(command));
+                        }
                     }
                 }
             }
@@ -385,6 +728,45 @@ where
         }
     }
 
+    fn handle_action_by_mode(&mut self, action: Action) -> EventResponse {
+        match self.mode {
+            PickerMode::Search => self.search_mode_handle_action(action),
+            PickerMode::Editing => self.editing_mode_handle_action(action),
+            PickerMode::Help => self.help_mode_handle_action(action),
+        }
+    }
+
+    /// Perform an action in the current mode, exactly as if a key bound to it was pressed
+    ///
+    /// This lets applications drive the picker from their own input handling or script it. After
+    /// [`ActionOutcome::Accept`] the result is available from [`Picker::selected_items`].
+    ///
+    /// Commands of execute actions are run attached to the terminal and waited for. Become actions
+    /// are accepted, their command is left to the caller to run, see
+    /// [`Picker::take_become_command`].
+    pub fn dispatch(&mut self, action: Action) -> ActionOutcome {
+        match self.handle_action_by_mode(action) {
+            EventResponse::NoAction | EventResponse::UpdateUI => ActionOutcome::Continue,
+            EventResponse::ExitProgram => ActionOutcome::Abort,
+            EventResponse::ReturnSelectedItems => ActionOutcome::Accept,
+            EventResponse::Execute(command) => {
+                let _ = run_in_foreground(command);
+                self.preview.refresh();
+                self.update_preview();
+                ActionOutcome::Continue
+            }
+            EventResponse::Become(command) => {
+                self.become_command = Some(command);
+                ActionOutcome::Accept
+            }
+        }
+    }
+
+    /// Returns the command of the become action that accepted the picker, if any
+    pub fn take_become_command(&mut self) -> Option<Command> {
+        self.become_command.take()
+    }
+
     pub fn set_autocomplete<F>(&mut self, autocomplete: F)
     where
         F: Fn(&str) -> RequestedItems<String> + Send + Sync + 'static,
@@ -399,3 +781,166 @@ where
         }
     }
 }
+
+/// Switch the terminal to the alternate screen in raw mode for drawing the picker
+fn enter_terminal(mouse_enabled: bool) -> io::Result<()> {
+    enable_raw_mode()?;
+    let mut stream = io::stderr();
+    execute!(stream, EnterAlternateScreen)?;
+    if mouse_enabled {
+        execute!(stream, EnableMouseCapture)?;
+    }
+    Ok(())
+}
+
+/// Give the terminal back in the state it was in before the picker started
+fn leave_terminal(mouse_enabled: bool) -> io::Result<()> {
+    disable_raw_mode()?;
+    let mut stream = io::stderr();
+    if mouse_enabled {
+        execute!(stream, DisableMouseCapture)?;
+    }
+    execute!(stream, LeaveAlternateScreen, Show)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Interactive picker whose source lists the same items for every query and records the
+    /// queries it was run with
+    fn interactive_picker() -> (Picker<String>, Arc<Mutex<Vec<String>>>) {
+        let mut picker = Picker::<String>::builder()
+            .use_config_file(false)
+            .reload_delay(Duration::ZERO)
+            .build()
+            .unwrap();
+        let queries = Arc::new(Mutex::new(Vec::new()));
+        let recorded = queries.clone();
+        picker.set_interactive(true);
+        picker.set_source(move |context: &SourceContext, injector: &Injector<_>| {
+            recorded.lock().unwrap().push(context.query.clone());
+            for item in ["one", "two", "three"] {
+                injector.push(
+                    SelectableItem::new(item.to_string()),
+                    SelectableItem::fill_columns,
+                );
+            }
+        });
+        picker.wait_for_matches();
+        (picker, queries)
+    }
+
+    fn type_text(picker: &mut Picker<String>, text: &str) {
+        for c in text.chars() {
+            picker.dispatch(Action::Insert(c));
+        }
+    }
+
+    #[test]
+    fn become_returns_a_command_that_outlives_the_picker() {
+        let mut picker = Picker::<String>::builder()
+            .use_config_file(false)
+            .build()
+            .unwrap();
+        picker.inject_items(|injector| {
+            injector.push(
+                SelectableItem::new("one".to_string()),
+                SelectableItem::fill_columns,
+            );
+        });
+        picker.wait_for_matches();
+
+        let outcome = picker.dispatch(Action::Become("cat {+f}".to_string()));
+        assert_eq!(outcome, ActionOutcome::Accept);
+        let command = picker.take_become_command().unwrap();
+        assert!(picker.take_become_command().is_none());
+        drop(picker);
+
+        let path = PathBuf::from(command.get_args().next().unwrap());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "one\n");
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn typing_reloads_with_the_query() {
+        let (mut picker, queries) = interactive_picker();
+        type_text(&mut picker, "ab");
+        picker.wait_for_matches();
+
+        assert_eq!(*queries.lock().unwrap(), ["", "ab"]);
+        assert_eq!(picker.snapshot().matched_item_count(), 3);
+    }
+
+    #[test]
+    fn toggling_drops_the_pending_reload() {
+        let (mut picker, queries) = interactive_picker();
+        type_text(&mut picker, "ab");
+        picker.dispatch(Action::ToggleInteractive);
+        type_text(&mut picker, "t");
+        picker.wait_for_matches();
+
+        // the fuzzy query filters the items without running the source
+        assert_eq!(*queries.lock().unwrap(), [""]);
+        assert_eq!(picker.snapshot().matched_item_count(), 2);
+    }
+
+    #[test]
+    fn toggling_keeps_multibyte_queries_editable() {
+        let (mut picker, _) = interactive_picker();
+        type_text(&mut picker, "né");
+        picker.dispatch(Action::ToggleInteractive);
+        type_text(&mut picker, "ü");
+        picker.dispatch(Action::ToggleInteractive);
+        assert_eq!(picker.query_index, 2);
+
+        picker.dispatch(Action::BackwardDeleteChar);
+        type_text(&mut picker, "ö");
+        picker.dispatch(Action::ToggleInteractive);
+        picker.dispatch(Action::BackwardDeleteChar);
+        picker.wait_for_matches();
+
+        assert_eq!(picker.query, "");
+        assert_eq!(picker.stashed_query, "nö");
+    }
+
+    #[test]
+    fn reload_while_filtering_uses_the_interactive_query() {
+        let (mut picker, queries) = interactive_picker();
+        type_text(&mut picker, "ab");
+        picker.wait_for_matches();
+        picker.dispatch(Action::ToggleInteractive);
+        type_text(&mut picker, "t");
+        picker.dispatch(Action::Reload);
+        picker.wait_for_matches();
+
+        assert_eq!(*queries.lock().unwrap(), ["", "ab", "ab"]);
+        assert_eq!(picker.query, "t");
+        assert_eq!(picker.snapshot().matched_item_count(), 2);
+    }
+
+    #[test]
+    fn reload_keeps_the_cursor_on_the_current_item() {
+        let (mut picker, _) = interactive_picker();
+        picker.dispatch(Action::Down);
+        assert_eq!(picker.current_item_text(), "two");
+
+        picker.dispatch(Action::Reload);
+        picker.wait_for_matches();
+        assert_eq!(picker.current_item_text(), "two");
+    }
+
+    #[test]
+    fn query_reload_moves_the_cursor_to_the_first_item() {
+        let (mut picker, _) = interactive_picker();
+        type_text(&mut picker, "x");
+        // the old items are still listed until the reload runs
+        picker.dispatch(Action::Down);
+        picker.wait_for_matches();
+
+        assert_eq!(picker.reload_cursor, None);
+        assert_eq!(picker.current_item_text(), "one");
+    }
+}
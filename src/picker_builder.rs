@@ -0,0 +1,463 @@
+use crate::{
+    action::Action,
+    config::Config,
+    keymap::{KeyChord, Keymap},
+    picker::PickerMode,
+    picker::{AppResult, AutocompleteFn, Picker},
+    picker_item::{Column, PickerItem},
+    placeholders::Delimiter,
+    preview_layout::PreviewLayout,
+    query::same_column_name,
+    requested_items::RequestedItems,
+};
+use nucleo::pattern::{CaseMatching, Normalization};
+use std::{marker::PhantomData, time::Duration};
+
+/// Configures a [`Picker`] from library code
+///
+/// Settings given to the builder take precedence over values from the on-disk config, which can be
+/// skipped entirely with [`PickerBuilder::use_config_file`]. All settings are validated once when
+/// calling [`PickerBuilder::build`].
+pub struct PickerBuilder<T> {
+    config: Option<Config>,
+    use_config_file: bool,
+    editable: bool,
+    height: Option<u16>,
+    wrap_around: Option<bool>,
+    mouse_enabled: Option<bool>,
+    invert_scroll: Option<bool>,
+    case_matching: Option<CaseMatching>,
+    normalization: Option<Normalization>,
+    preview_command: Option<String>,
+    use_shell: Option<bool>,
+    shell: Option<String>,
+    keep_colors: bool,
+    query: Option<String>,
+    expect_keys: Vec<KeyChord>,
+    select_one: bool,
+    exit_zero: bool,
+    preview_cache_size: Option<usize>,
+    preview_cache_ttl: Option<Duration>,
+    reload_delay: Option<Duration>,
+    preview_layout: Option<PreviewLayout>,
+    delimiter: Option<Delimiter>,
+    keymap: Option<Keymap>,
+    bindings: Vec<(PickerMode, KeyChord, Action)>,
+    field_names: Option<Vec<String>>,
+    autocomplete: Option<AutocompleteFn>,
+    columns: Vec<Column>,
+    default_column: Option<String>,
+    item_type: PhantomData<T>,
+}
+
+impl<T> Default for PickerBuilder<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> PickerBuilder<T> {
+    /// Create a builder that reads the on-disk config and allows editing
+    pub fn new() -> Self {
+        Self {
+            config: None,
+            use_config_file: true,
+            editable: true,
+            height: None,
+            wrap_around: None,
+            mouse_enabled: None,
+            invert_scroll: None,
+            case_matching: None,
+            normalization: None,
+            preview_command: None,
+            use_shell: None,
+            shell: None,
+            keep_colors: false,
+            query: None,
+            expect_keys: Vec::new(),
+            select_one: false,
+            exit_zero: false,
+            preview_cache_size: None,
+            preview_cache_ttl: None,
+            reload_delay: None,
+            preview_layout: None,
+            delimiter: None,
+            keymap: None,
+            bindings: Vec::new(),
+            field_names: None,
+            autocomplete: None,
+            columns: vec![Column::default()],
+            default_column: None,
+            item_type: PhantomData,
+        }
+    }
+
+    /// Whether the on-disk config and `PICLEO_` environment variables are consulted, defaults to true
+    pub fn use_config_file(mut self, use_config_file: bool) -> Self {
+        self.use_config_file = use_config_file;
+        self
+    }
+
+    /// Start from this config instead of reading it, e.g. one the application already loaded
+    ///
+    /// The other settings of the builder still take precedence over it.
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// Whether items can be edited and new items requested, defaults to true
+    pub fn editable(mut self, editable: bool) -> Self {
+        self.editable = editable;
+        self
+    }
+
+    /// Limit the picker interface to this many rows
+    pub fn height(mut self, height: u16) -> Self {
+        self.height = Some(height);
+        self
+    }
+
+    /// Wrap around when navigating past the first or last item
+    pub fn wrap_around(mut self, wrap_around: bool) -> Self {
+        self.wrap_around = Some(wrap_around);
+        self
+    }
+
+    /// Capture mouse events for scrolling and selecting items
+    pub fn mouse_enabled(mut self, mouse_enabled: bool) -> Self {
+        self.mouse_enabled = Some(mouse_enabled);
+        self
+    }
+
+    /// Invert the mouse scroll direction
+    pub fn invert_scroll(mut self, invert_scroll: bool) -> Self {
+        self.invert_scroll = Some(invert_scroll);
+        self
+    }
+
+    /// How the query treats upper and lower case characters
+    pub fn case_matching(mut self, case_matching: CaseMatching) -> Self {
+        self.case_matching = Some(case_matching);
+        self
+    }
+
+    /// How the query treats unicode normalization
+    pub fn normalization(mut self, normalization: Normalization) -> Self {
+        self.normalization = Some(normalization);
+        self
+    }
+
+    /// Command run for the current item, its output is shown next to the item list
+    pub fn preview_command(mut self, command: impl Into<String>) -> Self {
+        self.preview_command = Some(command.into());
+        self
+    }
+
+    /// Run commands through a shell so pipes, functions and aliases work, defaults to false
+    pub fn use_shell(mut self, use_shell: bool) -> Self {
+        self.use_shell = Some(use_shell);
+        self
+    }
+
+    /// Shell used when [`PickerBuilder::use_shell`] is enabled, defaults to `$SHELL`
+    pub fn shell(mut self, shell: impl Into<String>) -> Self {
+        self.shell = Some(shell.into());
+        self
+    }
+
+    /// Keep ANSI color codes in the preview output
+    pub fn keep_colors(mut self, keep_colors: bool) -> Self {
+        self.keep_colors = keep_colors;
+        self
+    }
+
+    /// Query the picker starts with
+    pub fn query(mut self, query: impl Into<String>) -> Self {
+        self.query = Some(query.into());
+        self
+    }
+
+    /// Keys that accept the selection and are reported by
+    /// [`SelectedItems::accepted_key`](crate::selected_items::SelectedItems::accepted_key)
+    pub fn expect_keys(mut self, keys: Vec<KeyChord>) -> Self {
+        self.expect_keys = keys;
+        self
+    }
+
+    /// Return the only matching item right away instead of showing the picker
+    pub fn select_one(mut self, select_one: bool) -> Self {
+        self.select_one = select_one;
+        self
+    }
+
+    /// Return [`PickerOutcome::NoMatch`](crate::picker::PickerOutcome::NoMatch) right away instead of
+    /// showing the picker when nothing matches
+    pub fn exit_zero(mut self, exit_zero: bool) -> Self {
+        self.exit_zero = exit_zero;
+        self
+    }
+
+    /// Number of preview outputs kept for reuse, 0 disables the cache
+    pub fn preview_cache_size(mut self, size: usize) -> Self {
+        self.preview_cache_size = Some(size);
+        self
+    }
+
+    /// How long a cached preview output is reused before the command is run again
+    pub fn preview_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.preview_cache_ttl = Some(ttl);
+        self
+    }
+
+    /// How long the query has to stay unchanged before the source is reloaded in interactive mode
+    pub fn reload_delay(mut self, delay: Duration) -> Self {
+        self.reload_delay = Some(delay);
+        self
+    }
+
+    /// Position, size and wrapping of the preview pane
+    pub fn preview_layout(mut self, layout: PreviewLayout) -> Self {
+        self.preview_layout = Some(layout);
+        self
+    }
+
+    /// How items are split into the fields used by placeholders like `{1}`, defaults to whitespace
+    pub fn delimiter(mut self, delimiter: Delimiter) -> Self {
+        self.delimiter = Some(delimiter);
+        self
+    }
+
+    /// Names for the fields of an item, letting commands use placeholders like `{path}`
+    pub fn field_names(mut self, field_names: Vec<String>) -> Self {
+        self.field_names = Some(field_names);
+        self
+    }
+
+    /// Replace the default keymap and any bindings from the config file
+    pub fn keymap(mut self, keymap: Keymap) -> Self {
+        self.keymap = Some(keymap);
+        self
+    }
+
+    /// Bind a key chord to an action in one mode, on top of the keymap
+    pub fn bind(mut self, mode: PickerMode, chord: KeyChord, action: Action) -> Self {
+        self.bindings.push((mode, chord, action));
+        self
+    }
+
+    /// Generate suggestions for the text typed in editing mode
+    pub fn autocomplete<F>(mut self, autocomplete: F) -> Self
+    where
+        F: Fn(&str) -> RequestedItems<String> + Send + Sync + 'static,
+    {
+        self.autocomplete = Some(Box::new(autocomplete));
+        self
+    }
+
+    /// Columns that items are split into, see [`PickerItem::columns`]
+    pub fn columns(mut self, columns: Vec<Column>) -> Self {
+        self.columns = columns;
+        self
+    }
+
+    /// Column searched by query terms without a `name:` prefix, defaults to the first column
+    pub fn default_column(mut self, name: impl Into<String>) -> Self {
+        self.default_column = Some(name.into());
+        self
+    }
+}
+
+impl<T> PickerBuilder<T>
+where
+    T: Sync + Send + PickerItem,
+{
+    /// Validate the settings and create the picker
+    pub fn build(self) -> AppResult<Picker<T>> {
+        let mut config = match self.config {
+            Some(config) => config,
+            None if self.use_config_file => Config::load()?,
+            None => Config::default(),
+        };
+
+        // builder settings override anything read from disk
+        if self.height.is_some() {
+            config.height = self.height;
+        }
+        if self.wrap_around.is_some() {
+            config.wrap_around = self.wrap_around;
+        }
+        if self.mouse_enabled.is_some() {
+            config.mouse_enabled = self.mouse_enabled;
+        }
+        if self.invert_scroll.is_some() {
+            config.invert_scroll = self.invert_scroll;
+        }
+        if self.preview_command.is_some() {
+            config.preview_command = self.preview_command;
+        }
+        if self.use_shell.is_some() {
+            config.use_shell = self.use_shell;
+        }
+        if self.shell.is_some() {
+            config.shell = self.shell;
+        }
+        if self.field_names.is_some() {
+            config.field_names = self.field_names;
+        }
+        if self.preview_cache_size.is_some() {
+            config.preview_cache_size = self.preview_cache_size;
+        }
+        if let Some(ttl) = self.preview_cache_ttl {
+            config.preview_cache_ttl = Some(ttl.as_millis() as u64);
+        }
+        if let Some(delay) = self.reload_delay {
+            config.reload_delay = Some(delay.as_millis() as u64);
+        }
+        config.validate()?;
+
+        if self.autocomplete.is_some() && !self.editable {
+            return Err("autocomplete requires the picker to be editable".into());
+        }
+        if self.columns.is_empty() {
+            return Err("at least one column is required".into());
+        }
+        for (index, column) in self.columns.iter().enumerate() {
+            if column.name.is_empty()
+                || column
+                    .name
+                    .contains(|c: char| c == ':' || c.is_whitespace())
+            {
+                return Err(format!(
+                    "invalid column name {:?}, names must not be empty or contain ':' or whitespace",
+                    column.name
+                )
+                .into());
+            }
+            // query prefixes ignore case, so names differing only in case couldn't be told apart
+            if self.columns[..index]
+                .iter()
+                .any(|other| same_column_name(&other.name, &column.name))
+            {
+                return Err(format!(
+                    "duplicate column name {:?}, names are compared ignoring case",
+                    column.name
+                )
+                .into());
+            }
+        }
+
+        let default_column = match &self.default_column {
+            Some(name) => self
+                .columns
+                .iter()
+                .position(|column| same_column_name(&column.name, name))
+                .ok_or_else(|| format!("default column {name:?} is not one of the columns"))?,
+            None => 0,
+        };
+
+        let mut picker = Picker::with_config(config, self.columns, self.editable);
+        picker.default_column = default_column;
+        if let Some(case_matching) = self.case_matching {
+            picker.case_matching = case_matching;
+        }
+        if let Some(normalization) = self.normalization {
+            picker.normalization = normalization;
+        }
+        picker.keep_colors = self.keep_colors;
+        picker.select_one = self.select_one;
+        picker.exit_zero = self.exit_zero;
+        if let Some(delimiter) = self.delimiter {
+            picker.delimiter = delimiter;
+        }
+        if let Some(keymap) = self.keymap {
+            picker.keymap = keymap;
+        }
+        for (mode, chord, action) in self.bindings {
+            picker.keymap.bind(mode, chord, action);
+        }
+        if let Some(preview_layout) = self.preview_layout {
+            picker.preview_layout = preview_layout;
+        }
+        picker.autocomplete = self.autocomplete;
+        picker.expect_keys = self.expect_keys;
+        if let Some(query) = self.query {
+            picker.set_query(query);
+        }
+
+        Ok(picker)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_with_columns(columns: Vec<Column>) -> AppResult<Picker<String>> {
+        PickerBuilder::new()
+            .use_config_file(false)
+            .columns(columns)
+            .build()
+    }
+
+    #[test]
+    fn column_names_must_differ_ignoring_case() {
+        let result = build_with_columns(vec![Column::new("title"), Column::hidden("Title")]);
+        assert!(result.is_err());
+
+        let result = build_with_columns(vec![Column::new("title"), Column::new("author")]);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn column_names_must_be_usable_as_prefixes() {
+        for name in ["", "a:b", "a b"] {
+            assert!(build_with_columns(vec![Column::new(name)]).is_err());
+        }
+        assert!(build_with_columns(Vec::new()).is_err());
+    }
+
+    #[test]
+    fn default_column_is_found_ignoring_case() {
+        let picker = PickerBuilder::<String>::new()
+            .use_config_file(false)
+            .columns(vec![Column::new("title"), Column::new("author")])
+            .default_column("Author")
+            .build()
+            .unwrap();
+        assert_eq!(picker.default_column, 1);
+
+        let result = PickerBuilder::<String>::new()
+            .use_config_file(false)
+            .default_column("missing")
+            .build();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn given_config_is_used_below_the_builder_settings() {
+        let config = Config {
+            height: Some(10),
+            reload_delay: Some(500),
+            ..Config::default()
+        };
+        let picker = PickerBuilder::<String>::new()
+            .config(config)
+            .height(20)
+            .build()
+            .unwrap();
+        assert_eq!(picker.config.height(), Some(20));
+        assert_eq!(picker.config.reload_delay(), Duration::from_millis(500));
+
+        let config = Config {
+            height: Some(0),
+            ..Config::default()
+        };
+        assert!(
+            PickerBuilder::<String>::new()
+                .config(config)
+                .build()
+                .is_err()
+        );
+    }
+}
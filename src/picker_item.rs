@@ -0,0 +1,64 @@
+use std::fmt::Display;
+
+/// An item that can be matched and displayed by the picker
+///
+/// Each item is split into one or more columns that line up with the [`Column`]s the picker was
+/// created with. Every type that implements [`Display`] is a single column item.
+pub trait PickerItem {
+    /// Returns the text of each column, in the same order as the picker's columns
+    fn columns(&self) -> Vec<String>;
+
+    /// Returns the text of the whole item, used when editing items and running commands
+    fn text(&self) -> String {
+        self.columns().join("\t")
+    }
+
+    /// Returns whether the text had to be decoded lossily, e.g. from input that isn't valid UTF-8
+    fn is_lossy(&self) -> bool {
+        false
+    }
+}
+
+impl<T: Display> PickerItem for T {
+    fn columns(&self) -> Vec<String> {
+        vec![self.to_string()]
+    }
+
+    fn text(&self) -> String {
+        self.to_string()
+    }
+}
+
+/// Describes one of the columns that items are split into
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Column {
+    /// Name of the column
+    pub name: String,
+
+    /// Whether the column is shown in the item list
+    pub visible: bool,
+}
+
+impl Column {
+    /// Create a column that is shown in the item list
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            visible: true,
+        }
+    }
+
+    /// Create a column that is matched against but not shown in the item list
+    pub fn hidden(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            visible: false,
+        }
+    }
+}
+
+impl Default for Column {
+    fn default() -> Self {
+        Self::new("text")
+    }
+}
@@ -0,0 +1,547 @@
+use regex::Regex;
+use std::{
+    collections::{HashMap, hash_map::DefaultHasher},
+    env,
+    fs::OpenOptions,
+    hash::{Hash, Hasher},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process,
+};
+
+/// How many file names are tried before giving up on creating a selection file
+const SELECTION_FILE_ATTEMPTS: u32 = 100;
+
+/// Separates the fields of an item that placeholders like `{1}` refer to
+#[derive(Debug, Clone, Default)]
+pub enum Delimiter {
+    /// Runs of whitespace, leading and trailing whitespace is ignored
+    #[default]
+    Whitespace,
+
+    /// Every occurrence of a literal string
+    Literal(String),
+
+    /// Every match of a regular expression
+    Regex(Regex),
+}
+
+impl Delimiter {
+    /// Split the text of an item into its fields
+    pub fn fields<'a>(&self, text: &'a str) -> Vec<&'a str> {
+        match self {
+            Delimiter::Whitespace => text.split_whitespace().collect(),
+            Delimiter::Literal(delimiter) => text.split(delimiter.as_str()).collect(),
+            Delimiter::Regex(regex) => regex.split(text).collect(),
+        }
+    }
+}
+
+/// Text of one item split into the fields that placeholders refer to
+#[derive(Default)]
+pub(crate) struct ItemFields<'a> {
+    /// Text of the whole item
+    pub(crate) text: &'a str,
+
+    /// Fields of the item text split with the configured delimiter, these must be slices of `text`
+    pub(crate) fields: Vec<&'a str>,
+
+    /// Value of each of the picker's columns for this item
+    pub(crate) columns: &'a [String],
+}
+
+impl<'a> ItemFields<'a> {
+    pub(crate) fn new(text: &'a str, columns: &'a [String], delimiter: &Delimiter) -> Self {
+        Self {
+            text,
+            fields: delimiter.fields(text),
+            columns,
+        }
+    }
+}
+
+/// Placeholders that take precedence over fields and columns with the same name
+const BUILT_IN_NAMES: &[&str] = &["q", "lines", "columns"];
+
+/// Values that the placeholders in a command are replaced with
+#[derive(Default)]
+pub(crate) struct Placeholders<'a> {
+    /// The current item
+    pub(crate) item: ItemFields<'a>,
+
+    /// The selected items, or the current item if nothing is selected
+    pub(crate) selected: Vec<ItemFields<'a>>,
+
+    /// File listing the selected items, only created when a command uses `{+f}`
+    pub(crate) selection_file: Option<&'a Path>,
+
+    /// Names given to the fields, e.g. from a header line
+    pub(crate) field_names: &'a [String],
+
+    /// Names of the picker's columns
+    pub(crate) column_names: Vec<&'a str>,
+
+    /// The current query
+    pub(crate) query: &'a str,
+
+    /// Lines and columns available in the preview pane
+    pub(crate) preview_size: (u16, u16),
+
+    /// Only replace `{q}`, for commands that don't run for an item so other braces are left as is
+    pub(crate) query_only: bool,
+}
+
+impl Placeholders<'_> {
+    /// Replace every placeholder in the command, placeholders without a value are left untouched
+    ///
+    /// Values are shell-escaped when `escape` is set since the command will be parsed by a shell.
+    /// Placeholders with several values, like `{+}`, are replaced by the values separated by spaces.
+    pub(crate) fn substitute(&self, command: &str, escape: bool) -> String {
+        let mut result = String::with_capacity(command.len());
+        let mut rest = command;
+
+        while let Some(start) = rest.find('{') {
+            result.push_str(&rest[..start]);
+            let after_brace = &rest[start + 1..];
+
+            match after_brace
+                .find('}')
+                .and_then(|end| Some((end, self.resolve(&after_brace[..end])?)))
+            {
+                Some((end, (values, raw))) => {
+                    let values: Vec<String> = if escape && !raw {
+                        values
+                            .into_iter()
+                            .map(|value| shell_escape::escape(value.into()).into_owned())
+                            .collect()
+                    } else {
+                        values
+                    };
+                    result.push_str(&values.join(" "));
+                    rest = &after_brace[end + 1..];
+                }
+                None => {
+                    // not a placeholder, keep the brace and look for the next one
+                    result.push('{');
+                    rest = after_brace;
+                }
+            }
+        }
+
+        result.push_str(rest);
+        result
+    }
+
+    /// Replace the placeholders in one argument of a command that is run without a shell
+    ///
+    /// An argument that is just a placeholder with several values becomes one argument per value.
+    pub(crate) fn arguments(&self, argument: &str) -> Vec<String> {
+        if let Some(placeholder) = argument
+            .strip_prefix('{')
+            .and_then(|argument| argument.strip_suffix('}'))
+            && !placeholder.contains(['{', '}'])
+            && let Some((values, _)) = self.resolve(placeholder)
+        {
+            return values;
+        }
+
+        vec![self.substitute(argument, false)]
+    }
+
+    /// Find the values of a placeholder and whether it was flagged to be inserted without escaping
+    fn resolve(&self, placeholder: &str) -> Option<(Vec<String>, bool)> {
+        if self.query_only {
+            return (placeholder == "q").then(|| (vec![self.query.to_string()], false));
+        }
+
+        if let Some(values) = self.values(placeholder) {
+            return Some((values, false));
+        }
+
+        // a leading `r` inserts the raw value, e.g. {r} or {r2..}
+        let values = self.values(placeholder.strip_prefix('r')?)?;
+        Some((values, true))
+    }
+
+    /// Look up the values of the placeholder with this name
+    fn values(&self, name: &str) -> Option<Vec<String>> {
+        // {+} and {+1} etc. apply to every selected item, {+f} is a file listing them
+        if let Some(name) = name.strip_prefix('+') {
+            if name == "f" {
+                let path = self.selection_file?;
+                return Some(vec![path.to_string_lossy().into_owned()]);
+            }
+            return self
+                .selected
+                .iter()
+                .map(|item| {
+                    self.named_value(item, name)
+                        .or_else(|| self.field_expression(item, name))
+                })
+                .collect();
+        }
+
+        let value = match self.named_value(&self.item, name) {
+            Some(value) => value,
+            None => match name {
+                "q" => self.query.to_string(),
+                "lines" => self.preview_size.0.to_string(),
+                "columns" => self.preview_size.1.to_string(),
+                _ => self.field_expression(&self.item, name)?,
+            },
+        };
+        Some(vec![value])
+    }
+
+    /// Look up the whole item, or one of its named fields or columns
+    ///
+    /// Fields and columns named like a built in placeholder can't be looked up, since names from a
+    /// header line must not change what `{q}`, `{lines}` or `{columns}` are replaced with.
+    fn named_value(&self, item: &ItemFields, name: &str) -> Option<String> {
+        // {} and {0} are the whole item
+        if name.is_empty() || name == "0" {
+            return Some(item.text.to_string());
+        }
+        if BUILT_IN_NAMES.contains(&name) {
+            return None;
+        }
+
+        // field names take precedence over the picker's column names
+        if let Some(index) = self.field_names.iter().position(|field| field == name) {
+            return Some(
+                item.fields
+                    .get(index)
+                    .copied()
+                    .unwrap_or_default()
+                    .to_string(),
+            );
+        }
+        let index = self
+            .column_names
+            .iter()
+            .position(|column| *column == name)?;
+        Some(item.columns.get(index).cloned().unwrap_or_default())
+    }
+
+    /// Evaluate a field expression such as `2`, `-1`, `2..`, `..3` or `1,3`
+    ///
+    /// Fields count from 1 and negative indices count back from the last field. Ranges are
+    /// inclusive and keep the delimiters between their fields, while the parts of a list are joined
+    /// with spaces. Fields past the end of the item are empty.
+    fn field_expression(&self, item: &ItemFields, expression: &str) -> Option<String> {
+        let parts = expression
+            .split(',')
+            .map(|part| field_part(item, part))
+            .collect::<Option<Vec<_>>>()?;
+        Some(parts.join(" "))
+    }
+}
+
+fn field_part<'a>(item: &ItemFields<'a>, part: &str) -> Option<&'a str> {
+    let last = item.fields.len() as isize - 1;
+    let Some((start, end)) = part.split_once("..") else {
+        let index = field_position(item, part.parse().ok()?)?;
+        return Some(
+            usize::try_from(index)
+                .ok()
+                .and_then(|index| item.fields.get(index))
+                .copied()
+                .unwrap_or_default(),
+        );
+    };
+
+    let start = match start {
+        "" => 0,
+        start => field_position(item, start.parse().ok()?)?.max(0),
+    };
+    let end = match end {
+        "" => last,
+        end => field_position(item, end.parse().ok()?)?.min(last),
+    };
+    if start > end {
+        return Some("");
+    }
+
+    // take the original text so the delimiters inside the range are kept
+    let first_field = item.fields[start as usize];
+    let last_field = item.fields[end as usize];
+    let range_start = field_offset(item, first_field);
+    let range_end = field_offset(item, last_field) + last_field.len();
+    Some(&item.text[range_start..range_end])
+}
+
+/// Convert a 1-based or negative field index into a position in `fields`, 0 is not a field
+fn field_position(item: &ItemFields, index: isize) -> Option<isize> {
+    match index {
+        0 => None,
+        index if index > 0 => Some(index - 1),
+        index => Some(item.fields.len() as isize + index),
+    }
+}
+
+/// Byte offset of a field within the item text
+fn field_offset(item: &ItemFields, field: &str) -> usize {
+    field.as_ptr() as usize - item.text.as_ptr() as usize
+}
+
+/// Write the text of the items to a temporary file, one item per line
+///
+/// `files` maps the hash of the contents to the files written so far, so an unchanged selection
+/// reuses the same file, which also keeps the preview cache working. New files are only readable by
+/// the current user and never replace an existing file, in case someone else created it in a shared
+/// temporary directory. Callers are responsible for removing the files.
+pub(crate) fn write_selection_file(
+    items: &[ItemFields],
+    files: &mut HashMap<u64, PathBuf>,
+) -> io::Result<PathBuf> {
+    let contents: String = items
+        .iter()
+        .map(|item| format!("{}\n", item.text))
+        .collect();
+
+    let mut hasher = DefaultHasher::new();
+    contents.hash(&mut hasher);
+    let hash = hasher.finish();
+    if let Some(path) = files.get(&hash) {
+        return Ok(path.clone());
+    }
+
+    for attempt in 0..SELECTION_FILE_ATTEMPTS {
+        let path = env::temp_dir().join(format!(
+            "picleo-{}-{hash:016x}-{attempt}.txt",
+            process::id()
+        ));
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+
+            options.mode(0o600);
+        }
+
+        let mut file = match options.open(&path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err),
+        };
+        files.insert(hash, path.clone());
+        file.write_all(contents.as_bytes())?;
+        return Ok(path);
+    }
+    Err(io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        "no unused name for the selection file",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_only_leaves_other_braces_alone() {
+        let placeholders = Placeholders {
+            query: "it's",
+            query_only: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            placeholders.substitute("find . -name {q} -exec stat {} + {1} {rq} {lines}", true),
+            "find . -name 'it'\\''s' -exec stat {} + {1} {rq} {lines}"
+        );
+        assert_eq!(placeholders.arguments("{q}"), ["it's"]);
+        assert_eq!(placeholders.arguments("{print $1}"), ["{print $1}"]);
+    }
+
+    fn item(text: &str) -> ItemFields<'_> {
+        ItemFields::new(text, &[], &Delimiter::Whitespace)
+    }
+
+    /// Placeholders for a current item split on `:`
+    fn for_item(text: &str) -> Placeholders<'_> {
+        Placeholders {
+            item: ItemFields::new(text, &[], &Delimiter::Literal(":".to_string())),
+            ..Default::default()
+        }
+    }
+
+    fn expand(placeholders: &Placeholders, command: &str) -> String {
+        placeholders.substitute(command, false)
+    }
+
+    #[test]
+    fn field_indices() {
+        let placeholders = for_item("a:b:c");
+        assert_eq!(expand(&placeholders, "{} {0}"), "a:b:c a:b:c");
+        assert_eq!(expand(&placeholders, "{1} {3}"), "a c");
+        assert_eq!(expand(&placeholders, "{-1} {-3}"), "c a");
+
+        // fields past either end are empty, there is no field 0 besides the whole item
+        assert_eq!(expand(&placeholders, "[{4}] [{-4}] [{-100}]"), "[] [] []");
+        assert_eq!(expand(&placeholders, "{x} {1a}"), "{x} {1a}");
+    }
+
+    #[test]
+    fn field_ranges_keep_the_delimiters() {
+        let placeholders = for_item("a:b:c:d");
+        assert_eq!(expand(&placeholders, "{2..3}"), "b:c");
+        assert_eq!(expand(&placeholders, "{2..} {..2}"), "b:c:d a:b");
+        assert_eq!(expand(&placeholders, "{..}"), "a:b:c:d");
+        assert_eq!(expand(&placeholders, "{-2..}"), "c:d");
+        assert_eq!(expand(&placeholders, "{2..-2}"), "b:c");
+
+        // ranges are clamped to the fields, and empty when the start is past the end
+        assert_eq!(expand(&placeholders, "{3..10} {-10..1}"), "c:d a");
+        assert_eq!(
+            expand(&placeholders, "[{3..2}] [{5..}] [{..-5}]"),
+            "[] [] []"
+        );
+        assert_eq!(expand(&placeholders, "{0..2} {1..x}"), "{0..2} {1..x}");
+    }
+
+    #[test]
+    fn field_ranges_of_whitespace_fields() {
+        let placeholders = Placeholders {
+            item: ItemFields::new("  one  two\tthree ", &[], &Delimiter::Whitespace),
+            ..Default::default()
+        };
+        assert_eq!(expand(&placeholders, "[{2..}]"), "[two\tthree]");
+        assert_eq!(expand(&placeholders, "[{1..2}]"), "[one  two]");
+    }
+
+    #[test]
+    fn field_lists() {
+        let placeholders = for_item("a:b:c:d");
+        assert_eq!(expand(&placeholders, "{1,3}"), "a c");
+        assert_eq!(expand(&placeholders, "{-1,1..2}"), "d a:b");
+        assert_eq!(expand(&placeholders, "{1,x}"), "{1,x}");
+    }
+
+    #[test]
+    fn escaping_and_raw_values() {
+        let placeholders = for_item("it's:a b");
+        assert_eq!(
+            placeholders.substitute("echo {1} {2} {r2}", true),
+            "echo 'it'\\''s' 'a b' a b"
+        );
+        assert_eq!(placeholders.substitute("echo {r}", true), "echo it's:a b");
+        assert_eq!(placeholders.arguments("{2}"), ["a b"]);
+        assert_eq!(placeholders.arguments("--name={2}"), ["--name=a b"]);
+    }
+
+    #[test]
+    fn unmatched_braces_are_kept() {
+        let placeholders = for_item("a:b");
+        assert_eq!(expand(&placeholders, "{ {1} }"), "{ a }");
+        assert_eq!(
+            expand(&placeholders, "awk '{print $1}' {1"),
+            "awk '{print $1}' {1"
+        );
+        assert_eq!(expand(&placeholders, "{{1}}"), "{a}");
+    }
+
+    #[test]
+    fn selected_items() {
+        let path = Path::new("/tmp/selection.txt");
+        let placeholders = Placeholders {
+            item: item("current"),
+            selected: vec![item("a 1"), item("b 2")],
+            selection_file: Some(path),
+            ..Default::default()
+        };
+        assert_eq!(placeholders.substitute("{+}", true), "'a 1' 'b 2'");
+        assert_eq!(expand(&placeholders, "{+2} {+-1}"), "1 2 1 2");
+        assert_eq!(expand(&placeholders, "{+f}"), "/tmp/selection.txt");
+        assert_eq!(placeholders.arguments("{+1}"), ["a", "b"]);
+        assert_eq!(placeholders.arguments("{r+}"), ["a 1", "b 2"]);
+
+        // without a selection file {+f} is left alone
+        let placeholders = Placeholders {
+            selected: vec![item("a")],
+            ..Default::default()
+        };
+        assert_eq!(expand(&placeholders, "{+f}"), "{+f}");
+    }
+
+    #[test]
+    fn named_fields_and_columns() {
+        let field_names = ["path".to_string(), "line".to_string()];
+        let columns = ["column value".to_string()];
+        let placeholders = Placeholders {
+            item: ItemFields::new(
+                "src/main.rs:12",
+                &columns,
+                &Delimiter::Literal(":".to_string()),
+            ),
+            field_names: &field_names,
+            column_names: vec!["title", "path"],
+            query: "main",
+            preview_size: (20, 80),
+            ..Default::default()
+        };
+        // field names take precedence over column names
+        assert_eq!(expand(&placeholders, "{path} {line}"), "src/main.rs 12");
+        assert_eq!(expand(&placeholders, "{title}"), "column value");
+        assert_eq!(expand(&placeholders, "{q} {lines} {columns}"), "main 20 80");
+    }
+
+    #[test]
+    fn built_in_placeholders_take_precedence_over_names() {
+        let field_names = ["lines".to_string(), "q".to_string()];
+        let columns = ["column value".to_string()];
+        let placeholders = Placeholders {
+            item: ItemFields::new("a b", &columns, &Delimiter::Whitespace),
+            selected: vec![ItemFields::new("c d", &columns, &Delimiter::Whitespace)],
+            field_names: &field_names,
+            column_names: vec!["columns"],
+            query: "main",
+            preview_size: (20, 80),
+            ..Default::default()
+        };
+        assert_eq!(expand(&placeholders, "{lines} {q} {columns}"), "20 main 80");
+        assert_eq!(
+            expand(&placeholders, "{+lines} {+columns}"),
+            "{+lines} {+columns}"
+        );
+        // the fields are still available by position
+        assert_eq!(expand(&placeholders, "{1} {2} {+1}"), "a b c");
+    }
+
+    #[test]
+    fn selection_file_is_reused_for_the_same_items() {
+        let mut files = HashMap::new();
+        let first = write_selection_file(&[item("a"), item("b")], &mut files).unwrap();
+        let same = write_selection_file(&[item("a"), item("b")], &mut files).unwrap();
+        let other = write_selection_file(&[item("c")], &mut files).unwrap();
+
+        assert_eq!(first, same);
+        assert_ne!(first, other);
+        assert_eq!(std::fs::read_to_string(&first).unwrap(), "a\nb\n");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            let mode = std::fs::metadata(&first).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        for path in files.values() {
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn selection_file_does_not_replace_existing_files() {
+        let mut files = HashMap::new();
+        let path = write_selection_file(&[item("existing")], &mut files).unwrap();
+
+        // a file someone else created under the same name is left alone
+        let mut fresh = HashMap::new();
+        let new_path = write_selection_file(&[item("existing")], &mut fresh).unwrap();
+        assert_ne!(path, new_path);
+        assert_eq!(std::fs::read_to_string(&new_path).unwrap(), "existing\n");
+
+        std::fs::remove_file(path).unwrap();
+        std::fs::remove_file(new_path).unwrap();
+    }
+}
@@ -0,0 +1,285 @@
+use crate::command::{kill_process_group, spawn_process_group};
+use eunicode::{raw_bytes::RawBytes, unicode_string::UnicodeString};
+use std::{
+    collections::VecDeque,
+    io::{self, Read},
+    process::{Child, Command, Stdio},
+    sync::{
+        Arc, Mutex,
+        mpsc::{self, Receiver, TryRecvError},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// How often a preview command whose output was closed is checked for having exited
+const REAP_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Runs preview commands on a worker thread so slow commands don't block the UI
+pub(crate) struct PreviewRunner {
+    /// Command line of the most recently requested preview
+    requested: Option<String>,
+    job: Option<PreviewJob>,
+    cache: PreviewCache,
+}
+
+struct PreviewJob {
+    key: String,
+    child: Arc<Mutex<Child>>,
+    receiver: Receiver<String>,
+}
+
+impl PreviewRunner {
+    pub(crate) fn new(cache_size: usize, cache_ttl: Duration) -> Self {
+        Self {
+            requested: None,
+            job: None,
+            cache: PreviewCache::new(cache_size, cache_ttl),
+        }
+    }
+
+    /// Start running the command unless it's the same as the last requested command
+    ///
+    /// Any preview that is still running is killed and its output is discarded. Returns the output
+    /// right away if the same command was run recently.
+    pub(crate) fn request(
+        &mut self,
+        mut command: Command,
+        keep_colors: bool,
+    ) -> io::Result<Option<String>> {
+        let key = command_key(&command);
+        if self.requested.as_ref() == Some(&key) {
+            return Ok(None);
+        }
+        self.cancel();
+
+        if let Some(output) = self.cache.get(&key) {
+            self.requested = Some(key);
+            return Ok(Some(output));
+        }
+        self.requested = Some(key.clone());
+
+        let mut child = spawn_process_group(
+            command
+                .stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped()),
+        )?;
+        let stdout = child.stdout.take();
+        let stderr = child.stderr.take();
+        let child = Arc::new(Mutex::new(child));
+        let (sender, receiver) = mpsc::channel();
+
+        let worker_child = child.clone();
+        thread::spawn(move || {
+            // read stderr separately so a full pipe can't block the command
+            let stderr_reader = thread::spawn(move || read_all(stderr));
+            let stdout_bytes = read_all(stdout);
+            let stderr_bytes = stderr_reader.join().unwrap_or_default();
+
+            // the receiver is gone if this preview was cancelled
+            let _ = sender.send(clean_output(stdout_bytes, stderr_bytes, keep_colors));
+
+            // reap the process, which can keep running after closing its output. The lock is only
+            // held to check on it, so cancelling the preview never waits for the process to exit
+            loop {
+                let status = worker_child.lock().map(|mut child| child.try_wait());
+                match status {
+                    Ok(Ok(None)) => thread::sleep(REAP_POLL_INTERVAL),
+                    _ => break,
+                }
+            }
+        });
+
+        self.job = Some(PreviewJob {
+            key,
+            child,
+            receiver,
+        });
+        Ok(None)
+    }
+
+    /// Returns the output of the running preview once it has finished
+    pub(crate) fn poll(&mut self) -> Option<String> {
+        let job = self.job.as_ref()?;
+        match job.receiver.try_recv() {
+            Ok(output) => {
+                if let Some(job) = self.job.take() {
+                    self.cache.insert(job.key, output.clone());
+                }
+                Some(output)
+            }
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => {
+                self.job = None;
+                None
+            }
+        }
+    }
+
+    /// Whether a preview command is currently running
+    pub(crate) fn is_loading(&self) -> bool {
+        self.job.is_some()
+    }
+
+    /// Forget all previews so the next request runs its command again
+    pub(crate) fn refresh(&mut self) {
+        self.cancel();
+        self.cache.entries.clear();
+    }
+
+    /// Kill the running preview command, if any, and forget the last request
+    pub(crate) fn cancel(&mut self) {
+        self.requested = None;
+        if let Some(job) = self.job.take()
+            && let Ok(mut child) = job.child.lock()
+        {
+            kill_process_group(&mut child);
+        }
+    }
+}
+
+impl Drop for PreviewRunner {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+/// Keeps the output of recent preview commands, keyed by the full command line
+struct PreviewCache {
+    /// Entries ordered from least to most recently used
+    entries: VecDeque<CacheEntry>,
+    capacity: usize,
+    ttl: Duration,
+}
+
+struct CacheEntry {
+    key: String,
+    output: String,
+    created: Instant,
+}
+
+impl PreviewCache {
+    fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            entries: VecDeque::with_capacity(capacity),
+            capacity,
+            ttl,
+        }
+    }
+
+    fn get(&mut self, key: &str) -> Option<String> {
+        // expired entries are dropped rather than returned
+        self.entries
+            .retain(|entry| entry.created.elapsed() < self.ttl);
+
+        let position = self.entries.iter().position(|entry| entry.key == key)?;
+        let entry = self.entries.remove(position)?;
+        let output = entry.output.clone();
+        self.entries.push_back(entry);
+        Some(output)
+    }
+
+    fn insert(&mut self, key: String, output: String) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        self.entries.retain(|entry| entry.key != key);
+        if self.entries.len() >= self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(CacheEntry {
+            key,
+            output,
+            created: Instant::now(),
+        });
+    }
+}
+
+/// Describe the command, including its environment, so identical commands can be detected
+fn command_key(command: &Command) -> String {
+    let mut key = String::new();
+    for (name, value) in command.get_envs() {
+        key.push_str(&format!("{}={:?} ", name.to_string_lossy(), value));
+    }
+    key.push_str(&format!("{:?}", command.get_program()));
+    for arg in command.get_args() {
+        key.push_str(&format!(" {:?}", arg));
+    }
+    key
+}
+
+fn read_all(reader: Option<impl Read>) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    if let Some(mut reader) = reader {
+        let _ = reader.read_to_end(&mut bytes);
+    }
+    bytes
+}
+
+fn clean_output(stdout: Vec<u8>, stderr: Vec<u8>, keep_colors: bool) -> String {
+    // TODO make this string safe for display
+    //      handle odd bytes
+    //      remove ansi codes except colors
+    //      clean unicode?
+
+    let mut preview_bytes: Vec<u8> = stdout;
+
+    // handle output on STDERR
+    if !stderr.is_empty() {
+        if !preview_bytes.is_empty() {
+            preview_bytes.extend_from_slice("\n--- stderr ---\n".as_bytes());
+        }
+        preview_bytes.extend_from_slice(&stderr);
+    }
+
+    // clean ANSI escapes via the `eunicode` crate, optionally keep colors
+    let raw_bytes = RawBytes::from_bytes(preview_bytes).strip_ansi_escapes(keep_colors);
+    // clean sketchy unicode codepoints
+    UnicodeString::new(raw_bytes).clean().into_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Command that closes its output right away and keeps running for a while
+    #[cfg(unix)]
+    fn detached_command() -> Command {
+        let mut command = Command::new("sh");
+        command.args(["-c", "echo started; exec >&- 2>&-; sleep 5"]);
+        command
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn output_arrives_once_it_is_closed() {
+        let mut runner = PreviewRunner::new(0, Duration::ZERO);
+        assert_eq!(runner.request(detached_command(), false).unwrap(), None);
+
+        let start = Instant::now();
+        let output = loop {
+            if let Some(output) = runner.poll() {
+                break output;
+            }
+            assert!(start.elapsed() < Duration::from_secs(2), "no output");
+            thread::sleep(Duration::from_millis(10));
+        };
+        assert_eq!(output, "started\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn cancel_does_not_wait_for_the_command() {
+        let mut runner = PreviewRunner::new(0, Duration::ZERO);
+        runner.request(detached_command(), false).unwrap();
+        // give the worker time to read the closed output and start reaping
+        thread::sleep(Duration::from_millis(200));
+
+        let start = Instant::now();
+        runner.cancel();
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert!(!runner.is_loading());
+    }
+}
@@ -0,0 +1,167 @@
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use std::str::FromStr;
+
+/// Smallest width or height the item list and the preview may each be given before the layout
+/// falls back to something that fits
+const MIN_SPLIT_COLUMNS: u16 = 30;
+const MIN_SPLIT_LINES: u16 = 8;
+
+/// Side of the screen that the preview pane is shown on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreviewPosition {
+    Right,
+    Left,
+    Top,
+    Bottom,
+}
+
+impl PreviewPosition {
+    /// The position after this one when cycling through the layouts
+    pub fn next(self) -> Self {
+        match self {
+            PreviewPosition::Right => PreviewPosition::Bottom,
+            PreviewPosition::Bottom => PreviewPosition::Left,
+            PreviewPosition::Left => PreviewPosition::Top,
+            PreviewPosition::Top => PreviewPosition::Right,
+        }
+    }
+
+    fn is_horizontal(self) -> bool {
+        matches!(self, PreviewPosition::Right | PreviewPosition::Left)
+    }
+}
+
+/// Space given to the preview pane
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreviewSize {
+    /// Percentage of the picker's width or height
+    Percent(u16),
+
+    /// Fixed number of columns or lines, including the border
+    Fixed(u16),
+}
+
+/// Where and how the preview pane is shown
+///
+/// Parsed from a comma separated spec like `right,50%,wrap` where every part is optional: a
+/// position (`right`, `left`, `top`, `bottom`), a size (`40%` or `60`), `wrap` or `nowrap`, and
+/// `hidden` to start with the preview hidden.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PreviewLayout {
+    pub position: PreviewPosition,
+    pub size: PreviewSize,
+    pub wrap: bool,
+    pub hidden: bool,
+}
+
+impl Default for PreviewLayout {
+    fn default() -> Self {
+        Self {
+            position: PreviewPosition::Right,
+            size: PreviewSize::Percent(50),
+            wrap: true,
+            hidden: false,
+        }
+    }
+}
+
+impl PreviewLayout {
+    /// Split the area into the item list and the preview pane
+    ///
+    /// Terminals too narrow for a side by side split show the preview below the items instead, and
+    /// the preview is left out entirely when neither split leaves both sides usable.
+    pub fn split(&self, area: Rect) -> (Rect, Option<Rect>) {
+        if self.hidden {
+            return (area, None);
+        }
+
+        let mut position = self.position;
+        if position.is_horizontal() && area.width < MIN_SPLIT_COLUMNS * 2 {
+            position = PreviewPosition::Bottom;
+        }
+        if !position.is_horizontal() && area.height < MIN_SPLIT_LINES * 2 {
+            return (area, None);
+        }
+
+        let (total, minimum) = if position.is_horizontal() {
+            (area.width, MIN_SPLIT_COLUMNS)
+        } else {
+            (area.height, MIN_SPLIT_LINES)
+        };
+        let preview_size = match self.size {
+            PreviewSize::Percent(percent) => (total as u32 * percent as u32 / 100) as u16,
+            PreviewSize::Fixed(size) => size,
+        }
+        .clamp(minimum, total - minimum);
+
+        let direction = if position.is_horizontal() {
+            Direction::Horizontal
+        } else {
+            Direction::Vertical
+        };
+        let preview_first = matches!(position, PreviewPosition::Left | PreviewPosition::Top);
+        let constraints = if preview_first {
+            [Constraint::Length(preview_size), Constraint::Min(1)]
+        } else {
+            [Constraint::Min(1), Constraint::Length(preview_size)]
+        };
+        let chunks = Layout::default()
+            .direction(direction)
+            .constraints(constraints)
+            .split(area);
+
+        if preview_first {
+            (chunks[1], Some(chunks[0]))
+        } else {
+            (chunks[0], Some(chunks[1]))
+        }
+    }
+}
+
+impl FromStr for PreviewLayout {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let mut layout = PreviewLayout::default();
+
+        for part in spec
+            .split(',')
+            .map(str::trim)
+            .filter(|part| !part.is_empty())
+        {
+            match part.to_lowercase().as_str() {
+                "right" => layout.position = PreviewPosition::Right,
+                "left" => layout.position = PreviewPosition::Left,
+                "top" | "up" => layout.position = PreviewPosition::Top,
+                "bottom" | "down" => layout.position = PreviewPosition::Bottom,
+                "wrap" => layout.wrap = true,
+                "nowrap" => layout.wrap = false,
+                "hidden" => layout.hidden = true,
+                size => {
+                    layout.size = match size.strip_suffix('%') {
+                        Some(percent) => match percent.parse() {
+                            Ok(percent) if (1..100).contains(&percent) => {
+                                PreviewSize::Percent(percent)
+                            }
+                            _ => {
+                                return Err(format!(
+                                    "invalid preview size {part:?}, expected a percentage between 1% and 99%"
+                                ));
+                            }
+                        },
+                        None => match size.parse() {
+                            Ok(size) if size > 0 => PreviewSize::Fixed(size),
+                            _ => {
+                                return Err(format!(
+                                    "invalid preview window option {part:?}, expected a position, size, wrap, nowrap or hidden"
+                                ));
+                            }
+                        },
+                    }
+                }
+            }
+        }
+
+        Ok(layout)
+    }
+}
@@ -0,0 +1,123 @@
+/// Split a query into one pattern string per column
+///
+/// Tokens are separated by unescaped whitespace. A token of the form `name:pattern` is sent to the
+/// column with that name (ignoring case), every other token is sent to `default_column`. A prefix
+/// that does not name a column is treated as part of the search text.
+pub fn column_queries(query: &str, column_names: &[&str], default_column: usize) -> Vec<String> {
+    let mut queries = vec![String::new(); column_names.len()];
+
+    for token in tokens(query) {
+        let (column, pattern) = match token.split_once(':') {
+            Some((prefix, pattern)) => match column_names
+                .iter()
+                .position(|name| same_column_name(name, prefix))
+            {
+                Some(column) => (column, pattern),
+                None => (default_column, token),
+            },
+            None => (default_column, token),
+        };
+
+        // a bare prefix such as `title:` doesn't restrict the column yet
+        if pattern.is_empty() {
+            continue;
+        }
+
+        if let Some(column_query) = queries.get_mut(column) {
+            if !column_query.is_empty() {
+                column_query.push(' ');
+            }
+            column_query.push_str(pattern);
+        }
+    }
+
+    queries
+}
+
+/// Whether two column names refer to the same column, which ignores ASCII case
+pub(crate) fn same_column_name(name: &str, other: &str) -> bool {
+    name.eq_ignore_ascii_case(other)
+}
+
+/// Split the query on whitespace that isn't escaped with a backslash, keeping the escapes intact
+fn tokens(query: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut token_start = None;
+    let mut escaped = false;
+
+    for (i, c) in query.char_indices() {
+        if c.is_whitespace() && !escaped {
+            if let Some(start) = token_start.take() {
+                tokens.push(&query[start..i]);
+            }
+        } else if token_start.is_none() {
+            token_start = Some(i);
+        }
+        escaped = c == '\\' && !escaped;
+    }
+
+    if let Some(start) = token_start {
+        tokens.push(&query[start..]);
+    }
+
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COLUMNS: [&str; 3] = ["title", "author", "hash"];
+
+    #[test]
+    fn unprefixed_terms_go_to_the_default_column() {
+        assert_eq!(column_queries("foo bar", &COLUMNS, 0), ["foo bar", "", ""]);
+        assert_eq!(column_queries("foo", &COLUMNS, 1), ["", "foo", ""]);
+        assert_eq!(column_queries("", &COLUMNS, 0), ["", "", ""]);
+    }
+
+    #[test]
+    fn prefixed_terms_go_to_their_column() {
+        assert_eq!(
+            column_queries("foo author:!bob hash:^ab author:ann", &COLUMNS, 0),
+            ["foo", "!bob ann", "^ab"]
+        );
+        // only the first colon separates the column name
+        assert_eq!(column_queries("title:a:b", &COLUMNS, 1), ["a:b", "", ""]);
+    }
+
+    #[test]
+    fn prefixes_ignore_case() {
+        assert_eq!(column_queries("AUTHOR:bob", &COLUMNS, 0), ["", "bob", ""]);
+        assert!(same_column_name("Title", "tITLE"));
+        assert!(!same_column_name("title", "titles"));
+    }
+
+    #[test]
+    fn unknown_prefixes_are_search_text() {
+        assert_eq!(
+            column_queries("http://example foo:bar", &COLUMNS, 0),
+            ["http://example foo:bar", "", ""]
+        );
+        assert_eq!(column_queries(":foo", &COLUMNS, 0), [":foo", "", ""]);
+    }
+
+    #[test]
+    fn bare_prefixes_are_ignored() {
+        assert_eq!(column_queries("foo author:", &COLUMNS, 0), ["foo", "", ""]);
+    }
+
+    #[test]
+    fn escaped_whitespace_stays_in_the_term() {
+        assert_eq!(
+            column_queries("title:foo\\ bar baz", &COLUMNS, 1),
+            ["foo\\ bar", "baz", ""]
+        );
+        // an escaped backslash doesn't escape the following space
+        assert_eq!(
+            column_queries("title:foo\\\\ bar", &COLUMNS, 1),
+            ["foo\\\\", "bar", ""]
+        );
+        assert_eq!(tokens("  a\\ b\\\\  c  "), ["a\\ b\\\\", "c"]);
+    }
+}
@@ -1,138 +1,69 @@
 use crate::{
-    picker::{EventResponse, Picker},
+    action::Action,
+    command::{ExecutionMode, run_in_background},
+    keymap::KeyChord,
+    picker::{EventResponse, Picker, PickerMode},
+    picker_item::PickerItem,
+    placeholders::{Delimiter, ItemFields, Placeholders, write_selection_file},
+    preview_layout::PreviewLayout,
+    query::column_queries,
     selectable::SelectableItem,
     selected_items::SelectedItems,
+    ui::preview_paragraph,
 };
-use comma::parse_command;
-use crossterm::event::{Event, KeyCode, KeyModifiers, MouseButton, MouseEventKind};
-use eunicode::{raw_bytes::RawBytes, unicode_string::UnicodeString};
-use nucleo::pattern::{CaseMatching, Normalization};
-use std::{fmt::Display, ops::RangeInclusive, process::Command};
+use crossterm::event::{Event, KeyModifiers, MouseButton, MouseEventKind};
+use ratatui::layout::Position;
+use std::{
+    hash::{DefaultHasher, Hash, Hasher},
+    io, mem,
+    ops::RangeInclusive,
+    process::Command,
+    time::Instant,
+};
+
+/// Number of lines the preview output takes up in a pane of a certain width
+pub(crate) struct PreviewLineCount {
+    /// Hash of the output that was counted
+    output_hash: u64,
+    width: u16,
+    wrap: bool,
+    lines: usize,
+}
 
 impl<T> Picker<T>
 where
-    T: Sync + Send + Display,
+    T: Sync + Send + PickerItem,
 {
     /// Handle event processing when we are in search mode
     pub(crate) fn search_mode_handle_event(&mut self, event: Event) -> EventResponse {
         let mut event_response: EventResponse;
 
         match event {
+            // expected keys accept the selection, whatever they are bound to
+            Event::Key(key) if self.expect_keys.contains(&key.into()) => {
+                self.accepted_key = Some(key.into());
+                event_response = EventResponse::ReturnSelectedItems;
+            }
             Event::Key(key) => {
-                event_response = EventResponse::UpdateUI;
-
-                match (key.code, key.modifiers) {
-                    (KeyCode::Char(key), KeyModifiers::NONE)
-                    | (KeyCode::Char(key), KeyModifiers::SHIFT) => {
-                        self.append_to_query(key);
-                        // NOTE: this probably doesn't need to saturate, that would require an absurdly long query
-                        self.query_index = self.query_index.saturating_add(1);
-                    }
-                    (KeyCode::Backspace, KeyModifiers::NONE) => {
-                        self.delete_from_query();
-                        // NOTE: this needs to saturate to handle deleting when the query is empty
-                        self.query_index = self.query_index.saturating_sub(1);
-                    }
-                    // TODO find out if it's a local keybinding that's preventing `Ctrl + Backspace` from working or if it's actually a bug
-                    (KeyCode::Backspace, KeyModifiers::CONTROL)
-                    | (KeyCode::Backspace, KeyModifiers::ALT) => {
-                        self.delete_word_backward();
-                    }
-                    (KeyCode::Right, KeyModifiers::NONE) => {
-                        // NOTE: this probably doesn't need to saturate, that would require an absurdly long query
-                        self.query_index = self.query_index.saturating_add(1);
-                    }
-                    (KeyCode::Right, KeyModifiers::CONTROL)
-                    | (KeyCode::Right, KeyModifiers::ALT) => {
-                        self.jump_word_forward();
-                    }
-                    (KeyCode::Left, KeyModifiers::NONE) => {
-                        // NOTE: this needs to saturate to handle deleting when the query is empty
-                        self.query_index = self.query_index.saturating_sub(1);
-                    }
-                    (KeyCode::Left, KeyModifiers::CONTROL) | (KeyCode::Left, KeyModifiers::ALT) => {
-                        self.jump_word_backward();
-                    }
-                    (KeyCode::Delete, KeyModifiers::CONTROL)
-                    | (KeyCode::Delete, KeyModifiers::ALT) => {
-                        self.delete_word_forward();
-                    }
-                    (KeyCode::Esc, KeyModifiers::NONE) => {
-                        if self.query_is_empty() {
-                            event_response = EventResponse::ExitProgram;
-                        } else {
-                            self.clear_query();
-                        }
-                    }
-                    (KeyCode::Char('c'), KeyModifiers::CONTROL) => {
-                        event_response = EventResponse::ExitProgram;
-                    }
-                    (KeyCode::Char('u'), KeyModifiers::CONTROL) => {
-                        self.clear_query();
-                        self.query_index = 0;
-                    }
-                    (KeyCode::Char('a'), KeyModifiers::CONTROL) => {
-                        self.query_index = 0;
-                    }
-                    (KeyCode::Char('e'), KeyModifiers::CONTROL) => {
-                        self.query_index = self.query.len();
-                    }
-                    (KeyCode::Char('k'), KeyModifiers::CONTROL) => {
-                        self.delete_to_end();
-                    }
-                    (KeyCode::Enter, KeyModifiers::NONE) => {
-                        // Print selected items and exit
-                        event_response = EventResponse::ReturnSelectedItems;
-                    }
-                    (KeyCode::Down, KeyModifiers::NONE) => {
-                        self.next();
-                    }
-                    (KeyCode::PageDown, KeyModifiers::NONE) => {
-                        self.next_page();
-                    }
-                    (KeyCode::End, KeyModifiers::NONE) => {
-                        self.end();
-                    }
-                    (KeyCode::Up, KeyModifiers::NONE) => {
-                        self.previous();
-                    }
-                    (KeyCode::PageUp, KeyModifiers::NONE) => {
-                        self.previous_page();
-                    }
-                    (KeyCode::Home, KeyModifiers::NONE) => {
-                        self.home();
-                    }
-                    (KeyCode::Tab, KeyModifiers::NONE) => {
-                        self.toggle_selected();
-                        self.next();
-                    }
-                    (KeyCode::Char('d'), KeyModifiers::CONTROL) => {
-                        if self.editable {
-                            self.enter_editing_mode(self.current_item_text());
-                        } else {
-                            event_response = EventResponse::NoAction;
-                        }
-                    }
-                    (KeyCode::Char('n'), KeyModifiers::CONTROL) => {
-                        if self.editable {
-                            self.enter_editing_mode(String::new());
-                        } else {
-                            event_response = EventResponse::NoAction;
-                        }
-                    }
-                    (KeyCode::Char('h'), KeyModifiers::CONTROL) => {
-                        self.enter_help_mode();
-                    }
-
-                    // ignore other key codes
-                    _ => {
-                        event_response = EventResponse::NoAction;
-                    }
-                }
+                event_response = match self.keymap.action(PickerMode::Search, key.into()) {
+                    Some(action) => self.search_mode_handle_action(action),
+                    None => EventResponse::NoAction,
+                };
             }
             Event::Mouse(mouse) => {
                 event_response = EventResponse::UpdateUI;
+                let over_preview = self.has_preview()
+                    && self
+                        .preview_area
+                        .contains(Position::new(mouse.column, mouse.row));
+
                 match mouse.kind {
+                    MouseEventKind::ScrollUp if over_preview => {
+                        self.scroll_preview(-3);
+                    }
+                    MouseEventKind::ScrollDown if over_preview => {
+                        self.scroll_preview(3);
+                    }
                     MouseEventKind::ScrollUp => {
                         // Check if Shift or Control is held for page navigation
                         if mouse.modifiers.contains(KeyModifiers::SHIFT)
@@ -188,20 +119,163 @@ where
         event_response
     }
 
+    /// Perform an action while searching
+    pub(crate) fn search_mode_handle_action(&mut self, action: Action) -> EventResponse {
+        match action {
+            Action::Insert(key) => {
+                self.append_to_query(key);
+                // NOTE: this probably doesn't need to saturate, that would require an absurdly long query
+                self.query_index = self.query_index.saturating_add(1);
+            }
+            Action::BackwardDeleteChar => {
+                self.delete_from_query();
+                // NOTE: this needs to saturate to handle deleting when the query is empty
+                self.query_index = self.query_index.saturating_sub(1);
+            }
+            Action::BackwardKillWord => {
+                self.delete_word_backward();
+            }
+            Action::ForwardChar => {
+                self.query_index = (self.query_index + 1).min(self.query.chars().count());
+            }
+            Action::ForwardWord => {
+                self.jump_word_forward();
+            }
+            Action::BackwardChar => {
+                // NOTE: this needs to saturate to handle deleting when the query is empty
+                self.query_index = self.query_index.saturating_sub(1);
+            }
+            Action::BackwardWord => {
+                self.jump_word_backward();
+            }
+            Action::KillWord => {
+                self.delete_word_forward();
+            }
+            Action::Cancel => {
+                if self.query_is_empty() {
+                    return EventResponse::ExitProgram;
+                }
+                self.clear_query();
+            }
+            Action::Abort => return EventResponse::ExitProgram,
+            Action::ClearQuery => {
+                self.clear_query();
+                self.query_index = 0;
+            }
+            Action::BeginningOfLine => {
+                self.query_index = 0;
+            }
+            Action::EndOfLine => {
+                self.query_index = self.query.chars().count();
+            }
+            Action::KillLine => {
+                self.delete_to_end();
+            }
+            // Print selected items and exit
+            Action::Accept => return EventResponse::ReturnSelectedItems,
+            Action::Down => {
+                self.next();
+            }
+            Action::PageDown => {
+                self.next_page();
+            }
+            Action::Last => {
+                self.end();
+            }
+            Action::Up => {
+                self.previous();
+            }
+            Action::PageUp => {
+                self.previous_page();
+            }
+            Action::First => {
+                self.home();
+            }
+            Action::PreviewUp => {
+                self.scroll_preview(-1);
+            }
+            Action::PreviewDown => {
+                self.scroll_preview(1);
+            }
+            Action::PreviewPageUp => {
+                self.scroll_preview(-(self.preview_size().0 as i32));
+            }
+            Action::PreviewPageDown => {
+                self.scroll_preview(self.preview_size().0 as i32);
+            }
+            Action::Toggle => {
+                self.toggle_selected();
+                self.update_preview();
+            }
+            Action::ToggleDown => {
+                self.toggle_selected();
+                self.next();
+            }
+            Action::EditItem if self.editable => {
+                self.enter_editing_mode(self.current_item_text());
+            }
+            Action::NewItem if self.editable => {
+                self.enter_editing_mode(String::new());
+            }
+            Action::Help => {
+                self.enter_help_mode();
+            }
+            Action::Reload => {
+                self.reload();
+            }
+            Action::ToggleInteractive => {
+                self.toggle_interactive();
+            }
+            Action::TogglePreview => {
+                self.toggle_preview();
+            }
+            Action::CyclePreview => {
+                self.cycle_preview_position();
+            }
+            Action::Execute(template) => {
+                return match self.build_command(&template) {
+                    Ok(Some(command)) => EventResponse::Execute(command),
+                    _ => EventResponse::NoAction,
+                };
+            }
+            Action::ExecuteSilent(template) => {
+                if let Ok(Some(command)) = self.build_command(&template) {
+                    let _ = run_in_background(command);
+                }
+                return EventResponse::NoAction;
+            }
+            Action::Become(template) => {
+                // the command only runs once the picker is dropped, which removes the selection files
+                // it tracks, so the file this command reads is left for the command
+                let selection_files = mem::take(&mut self.selection_files);
+                let command = self.build_command(&template);
+                self.selection_files = selection_files;
+                return match command {
+                    Ok(Some(command)) => EventResponse::Become(command),
+                    _ => EventResponse::NoAction,
+                };
+            }
+
+            // ignore actions that don't apply while searching
+            _ => return EventResponse::NoAction,
+        }
+
+        EventResponse::UpdateUI
+    }
+
+    /// Byte offset in the query of a cursor position, which counts characters
+    fn query_byte_index(&self, index: usize) -> usize {
+        self.query
+            .char_indices()
+            .nth(index)
+            .map_or(self.query.len(), |(i, _)| i)
+    }
+
     pub(crate) fn append_to_query(&mut self, key: char) {
         // TODO constrain selected item to match range
-        if self.query_index >= self.query.len() {
-            self.query.push(key);
-        } else {
-            self.query.insert(self.query_index, key);
-        }
-        self.matcher.pattern.reparse(
-            0,
-            &self.query,
-            CaseMatching::Smart,
-            Normalization::Smart,
-            true,
-        );
+        self.query
+            .insert(self.query_byte_index(self.query_index), key);
+        self.update_pattern();
         // ensure that the selection stays in range
         // TODO find a better way, ideally one that preserves the position as much as possible
         self.set_current_index(0, Some(false));
@@ -211,15 +285,10 @@ where
     pub(crate) fn delete_from_query(&mut self) {
         if self.query_index > 0 && !self.query.is_empty() {
             // Remove the character before the cursor
-            self.query.remove(self.query_index - 1);
-        }
-        self.matcher.pattern.reparse(
-            0,
-            &self.query,
-            CaseMatching::Smart,
-            Normalization::Smart,
-            false,
-        );
+            self.query
+                .remove(self.query_byte_index(self.query_index - 1));
+        }
+        self.update_pattern();
     }
 
     pub(crate) fn delete_word_backward(&mut self) {
@@ -228,7 +297,8 @@ where
         }
 
         // Get the part of the query before the current position
-        let before_cursor = &self.query[..self.query_index];
+        let cursor = self.query_byte_index(self.query_index);
+        let before_cursor = &self.query[..cursor];
 
         // Find the previous word boundary
         let chars: Vec<char> = before_cursor.chars().collect();
@@ -250,27 +320,26 @@ where
         }
 
         // Remove the characters between the new position and the old cursor position
-        self.query = format!("{}{}", &self.query[..pos], &self.query[self.query_index..]);
+        self.query = format!(
+            "{}{}",
+            &self.query[..self.query_byte_index(pos)],
+            &self.query[cursor..]
+        );
         self.query_index = pos;
 
         // Update the matcher
-        self.matcher.pattern.reparse(
-            0,
-            &self.query,
-            CaseMatching::Smart,
-            Normalization::Smart,
-            false,
-        );
+        self.update_pattern();
     }
 
     pub(crate) fn delete_word_forward(&mut self) {
         let query_len = self.query.len();
-        if self.query_index >= query_len {
+        let cursor = self.query_byte_index(self.query_index);
+        if cursor >= query_len {
             return;
         }
 
         // Start from current position
-        let remaining = &self.query[self.query_index..];
+        let remaining = &self.query[cursor..];
 
         // Find the next word boundary
         let mut chars = remaining.char_indices();
@@ -280,9 +349,9 @@ where
         if let Some((_, first_char)) = chars.next() {
             if !first_char.is_whitespace() {
                 // Skip until we hit whitespace or end
-                while let Some((i, c)) = chars.next() {
+                for (i, c) in chars.by_ref() {
                     if c.is_whitespace() {
-                        end_pos = self.query_index + i;
+                        end_pos = cursor + i;
                         break;
                     }
                 }
@@ -291,9 +360,9 @@ where
                 while let Some((_i, c)) = chars.next() {
                     if !c.is_whitespace() {
                         // Then skip until next whitespace or end
-                        while let Some((j, c2)) = chars.next() {
+                        for (j, c2) in chars.by_ref() {
                             if c2.is_whitespace() {
-                                end_pos = self.query_index + j;
+                                end_pos = cursor + j;
                                 break;
                             }
                         }
@@ -304,58 +373,43 @@ where
         }
 
         // Remove the characters between the cursor position and the end position
-        self.query = format!(
-            "{}{}",
-            &self.query[..self.query_index],
-            &self.query[end_pos..]
-        );
+        self.query = format!("{}{}", &self.query[..cursor], &self.query[end_pos..]);
 
         // Update the matcher
-        self.matcher.pattern.reparse(
-            0,
-            &self.query,
-            CaseMatching::Smart,
-            Normalization::Smart,
-            false,
-        );
+        self.update_pattern();
     }
 
     pub(crate) fn delete_to_end(&mut self) {
-        if self.query_index >= self.query.len() {
+        let cursor = self.query_byte_index(self.query_index);
+        if cursor >= self.query.len() {
             return;
         }
 
         // Truncate the query at the cursor position
-        self.query.truncate(self.query_index);
+        self.query.truncate(cursor);
 
         // Update the matcher
-        self.matcher.pattern.reparse(
-            0,
-            &self.query,
-            CaseMatching::Smart,
-            Normalization::Smart,
-            false,
-        );
+        self.update_pattern();
     }
 
     pub(crate) fn jump_word_forward(&mut self) {
-        let query_len = self.query.len();
+        let query_len = self.query.chars().count();
         if self.query_index >= query_len {
             return;
         }
 
         // Start from current position
-        let remaining = &self.query[self.query_index..];
+        let remaining = &self.query[self.query_byte_index(self.query_index)..];
 
         // Find the next word boundary
         let mut chars = remaining.char_indices();
 
         // Skip the current word if we're in the middle of one
-        while let Some((i, c)) = chars.next() {
+        for (i, c) in chars.by_ref() {
             if c.is_whitespace() {
                 break;
             }
-            if i == remaining.len() - 1 {
+            if i + c.len_utf8() == remaining.len() {
                 // If we reach the end of the string, set index to the end
                 self.query_index = query_len;
                 return;
@@ -364,12 +418,12 @@ where
 
         // Skip any whitespace
         let mut word_start = 0;
-        while let Some((i, c)) = chars.next() {
+        for (i, c) in chars {
             if !c.is_whitespace() {
                 word_start = i;
                 break;
             }
-            if i == remaining.len() - 1 {
+            if i + c.len_utf8() == remaining.len() {
                 // If we reach the end of the string, set index to the end
                 self.query_index = query_len;
                 return;
@@ -377,7 +431,7 @@ where
         }
 
         // Move to the start of the next word
-        self.query_index += word_start;
+        self.query_index += remaining[..word_start].chars().count();
     }
 
     pub(crate) fn jump_word_backward(&mut self) {
@@ -386,7 +440,8 @@ where
         }
 
         // Get the part of the query before the current position
-        let before_cursor = &self.query[..self.query_index];
+        let cursor = self.query_byte_index(self.query_index);
+        let before_cursor = &self.query[..cursor];
 
         // Find the previous word boundary
         let chars: Vec<char> = before_cursor.chars().collect();
@@ -410,6 +465,75 @@ where
         self.query_index = pos;
     }
 
+    /// Send the query to the matcher, splitting out tokens prefixed with a column name
+    pub(crate) fn update_pattern(&mut self) {
+        let queries = if self.interactive {
+            // the query is passed to the source instead, so every loaded item is shown
+            self.pending_reload = Some(Instant::now() + self.config.reload_delay());
+            vec![String::new(); self.columns.len()]
+        } else {
+            // a reload for an interactive query that was left must not run with this query
+            self.pending_reload = None;
+            let column_names: Vec<&str> = self.columns.iter().map(|c| c.name.as_str()).collect();
+            column_queries(&self.query, &column_names, self.default_column)
+        };
+
+        for (column, query) in queries.into_iter().enumerate() {
+            let previous = &self.column_patterns[column];
+            if *previous == query {
+                continue;
+            }
+
+            // nucleo can only reuse previous results when the old pattern is a prefix of the new one
+            let append = query.starts_with(previous.as_str()) && !previous.ends_with('\\');
+            self.matcher.pattern.reparse(
+                column,
+                &query,
+                self.case_matching,
+                self.normalization,
+                append,
+            );
+            self.column_patterns[column] = query;
+        }
+    }
+
+    /// Pass the query to the source on every change instead of filtering the loaded items
+    pub fn set_interactive(&mut self, interactive: bool) {
+        self.interactive = interactive;
+        self.update_pattern();
+    }
+
+    /// Switch between passing the query to the source and filtering the items it loaded
+    ///
+    /// Each side keeps its own query, so the source's query is restored when switching back.
+    /// Does nothing if the picker has no source.
+    pub fn toggle_interactive(&mut self) {
+        if self.source.is_none() {
+            return;
+        }
+
+        self.interactive = !self.interactive;
+        mem::swap(&mut self.query, &mut self.stashed_query);
+        self.query_index = self.query.chars().count();
+        self.update_pattern();
+        self.set_current_index(0, Some(false));
+        self.update_preview();
+    }
+
+    /// Replace the query, placing the cursor at its end
+    pub fn set_query(&mut self, query: impl Into<String>) {
+        self.query = query.into();
+        self.query_index = self.query.chars().count();
+        self.update_pattern();
+        self.set_current_index(0, Some(false));
+    }
+
+    /// Keys that accept the selection like the accept action, reported by
+    /// [`SelectedItems::accepted_key`] so callers can act differently on each
+    pub fn set_expect_keys(&mut self, keys: Vec<KeyChord>) {
+        self.expect_keys = keys;
+    }
+
     pub fn query_is_empty(&self) -> bool {
         self.query.is_empty()
     }
@@ -417,13 +541,7 @@ where
     pub(crate) fn clear_query(&mut self) {
         self.query.clear();
         // TODO seems like there should be a better way to clear the query
-        self.matcher.pattern.reparse(
-            0,
-            &self.query,
-            CaseMatching::Smart,
-            Normalization::Smart,
-            false,
-        );
+        self.update_pattern();
     }
 
     pub fn next(&mut self) {
@@ -498,13 +616,13 @@ where
 
     pub(crate) fn handle_item_click(&mut self, mouse_row: u16) {
         // Calculate which item was clicked based on the mouse row
-        // The items list starts at row 4 (help=1, search=3, items start at 4)
-        // and has a border, so the first item is at row 5
-        if mouse_row < 5 {
+        // The items list has a border, so the first item is one row below the top of its area
+        let first_item_row = self.items_area.y + 1;
+        if mouse_row < first_item_row || mouse_row >= self.items_area.bottom().saturating_sub(1) {
             return; // Click was not on an item
         }
 
-        let item_row = mouse_row - 5; // Adjust for UI layout
+        let item_row = mouse_row - first_item_row;
         let clicked_index = self.first_visible_item_index + item_row as u32;
 
         // Check if the clicked index is valid
@@ -517,6 +635,9 @@ where
         if let Some(item) = snapshot.get_matched_item(clicked_index) {
             item.data.toggle_selected();
         }
+
+        // commands using the selection need to see the change
+        self.update_preview();
     }
 
     pub fn toggle_selected(&mut self) {
@@ -595,20 +716,66 @@ where
 
         // Get the currently selected item's text
         if let Some(current_item) = snapshot.get_matched_item(self.current_index) {
-            current_item.data.to_string()
+            current_item.data.text()
         } else {
             String::new()
         }
     }
 
+    /// Returns the column values of the current item, empty if there is no current item
+    pub fn current_item_columns(&self) -> Vec<String> {
+        self.snapshot()
+            .get_matched_item(self.current_index)
+            .map(|item| item.data.columns())
+            .unwrap_or_default()
+    }
+
     pub fn set_preview_command(&mut self, command: String) {
         self.preview_command = Some(command);
     }
 
+    pub fn set_preview_layout(&mut self, layout: PreviewLayout) {
+        self.preview_layout = layout;
+    }
+
+    pub fn set_execution_mode(&mut self, execution_mode: ExecutionMode) {
+        self.execution_mode = execution_mode;
+    }
+
+    pub fn set_delimiter(&mut self, delimiter: Delimiter) {
+        self.delimiter = delimiter;
+    }
+
+    pub fn set_field_names(&mut self, field_names: Vec<String>) {
+        self.field_names = field_names;
+    }
+
     pub fn set_keep_colors(&mut self, keep_colors: bool) {
         self.keep_colors = keep_colors;
     }
 
+    /// Return the only matching item right away instead of showing the picker
+    pub fn set_select_one(&mut self, select_one: bool) {
+        self.select_one = select_one;
+    }
+
+    /// Return [`PickerOutcome::NoMatch`](crate::picker::PickerOutcome::NoMatch) when nothing
+    /// matches instead of showing the picker
+    pub fn set_exit_zero(&mut self, exit_zero: bool) {
+        self.exit_zero = exit_zero;
+    }
+
+    /// Show or hide the preview pane
+    pub fn toggle_preview(&mut self) {
+        self.preview_layout.hidden = !self.preview_layout.hidden;
+    }
+
+    /// Move the preview pane to the next side of the screen, showing it if it was hidden
+    pub fn cycle_preview_position(&mut self) {
+        self.preview_layout.position = self.preview_layout.position.next();
+        self.preview_layout.hidden = false;
+    }
+
     pub fn has_preview(&self) -> bool {
         self.preview_command.is_some()
     }
@@ -617,96 +784,171 @@ where
         &self.preview_output
     }
 
-    fn substitute_placeholders(&self, command: &str, item_text: &str, escape: bool) -> String {
-        let mut result = command.to_string();
+    /// Create a command from a template, filling in its placeholders for the current item
+    ///
+    /// Returns `None` if the template doesn't contain a command.
+    pub(crate) fn build_command(&mut self, template: &str) -> io::Result<Option<Command>> {
+        let item_text = self.current_item_text();
+        let item_columns = self.current_item_columns();
+
+        // selections are only collected when the command uses them
+        let selected: Vec<(String, Vec<String>)> =
+            if template.contains("{+") || template.contains("{r+") {
+                self.selected_items()
+                    .items()
+                    .iter()
+                    .map(|item| (item.text(), item.columns()))
+                    .collect()
+            } else {
+                Vec::new()
+            };
+        let selected: Vec<ItemFields> = selected
+            .iter()
+            .map(|(text, columns)| ItemFields::new(text, columns, &self.delimiter))
+            .collect();
 
-        // TODO make this more lazy/efficient
-        // Replace {} and {0} with the whole line (escaped)
-        let escaped_item_text = if escape {
-            shell_escape::escape(item_text.into())
+        let selection_file = if template.contains("+f}") {
+            Some(write_selection_file(&selected, &mut self.selection_files)?)
         } else {
-            item_text.into()
+            None
         };
-        result = result.replace("{}", &escaped_item_text);
-        result = result.replace("{0}", &escaped_item_text);
-
-        // TODO add support for user specified delimiters
-        // Split the item text by whitespace to get columns
-        let columns: Vec<&str> = item_text.split_whitespace().collect();
-
-        // TODO make this more efficient, instead of iterating look for the index in the pattern and pull by offset
-        // Replace {1}, {2}, etc. with column values (1-indexed, escaped)
-        for (i, column) in columns.iter().enumerate() {
-            let placeholder = format!("{{{}}}", i + 1);
-            let escaped_column = if escape {
-                shell_escape::escape((*column).into())
-            } else {
-                (*column).into()
-            };
-            result = result.replace(&placeholder, &escaped_column);
-        }
 
-        // TODO: Add support for named column placeholders like {column_name}
-        // This would require additional metadata about column names
+        let placeholders = Placeholders {
+            item: ItemFields::new(&item_text, &item_columns, &self.delimiter),
+            selected,
+            selection_file: selection_file.as_deref(),
+            field_names: &self.field_names,
+            column_names: self
+                .columns
+                .iter()
+                .map(|column| column.name.as_str())
+                .collect(),
+            query: &self.query,
+            preview_size: self.preview_size(),
+            query_only: false,
+        };
+        Ok(self.execution_mode.command(template, &placeholders))
+    }
 
-        result
+    /// Returns the number of lines and columns available for preview output
+    pub fn preview_size(&self) -> (u16, u16) {
+        // account for the borders around the preview
+        (
+            self.preview_area.height.saturating_sub(2),
+            self.preview_area.width.saturating_sub(2),
+        )
     }
 
     pub fn update_preview(&mut self) {
         if let Some(ref command) = self.preview_command.clone() {
             let item_text = self.current_item_text();
-            if item_text.is_empty() {
-                self.preview_output.clear();
+
+            // start new items at the top of their preview
+            if item_text != self.preview_item_text {
+                self.preview_scroll_offset = 0;
+                self.preview_item_text = item_text.clone();
             }
 
-            if let Some(command_parts) = parse_command(&command) {
-                let mut command_parts_iter = command_parts.iter();
-                if let Some(program) = command_parts_iter.next() {
-                    // we are substituting args separately to minimize whitespace issues
-                    // we could also substitute the whole command while injecting quoted strings and then split
-                    let args: Vec<String> = command_parts_iter
-                        .map(|arg| self.substitute_placeholders(arg, &item_text, false))
-                        .collect();
-
-                    match Command::new(program).args(&args).output() {
-                        Ok(output) => {
-                            // TODO make this string safe for display
-                            //      handle odd bytes
-                            //      remove ansi codes except colors
-                            //      clean unicode?
-
-                            let mut preview_bytes: Vec<u8> = output.stdout;
-
-                            // handle output on STDERR
-                            if !output.stderr.is_empty() {
-                                if !preview_bytes.is_empty() {
-                                    preview_bytes
-                                        .extend_from_slice("\n--- stderr ---\n".as_bytes());
-                                }
-                                preview_bytes.extend_from_slice(&output.stderr);
-                            }
+            // nothing needs to run while the preview pane is hidden
+            if item_text.is_empty() || self.preview_area.is_empty() {
+                self.preview.cancel();
+                self.preview_output.clear();
+                return;
+            }
 
-                            // clean ANSI escapes via the `eunicode` crate, optionally keep colors
-                            let raw_bytes = RawBytes::from_bytes(preview_bytes)
-                                .strip_ansi_escapes(self.keep_colors);
-                            // clean sketchy unicode codepoints
-                            self.preview_output =
-                                UnicodeString::new(raw_bytes).clean().into_string();
-                            return;
-                        }
-                        Err(e) => {
-                            self.preview_output = format!("Error executing preview command: {}", e);
-                            return;
-                        }
+            let preview_command = match self.build_command(command) {
+                Ok(preview_command) => preview_command,
+                Err(e) => {
+                    self.preview.cancel();
+                    self.preview_output = format!("Error executing preview command: {}", e);
+                    return;
+                }
+            };
+            if let Some(mut preview_command) = preview_command {
+                // let commands size their output to fit the preview pane
+                let (lines, columns) = self.preview_size();
+                preview_command
+                    .env("PICLEO_PREVIEW_LINES", lines.to_string())
+                    .env("PICLEO_PREVIEW_COLUMNS", columns.to_string());
+
+                // the command runs in the background, the previous output stays visible until it finishes
+                match self.preview.request(preview_command, self.keep_colors) {
+                    Ok(Some(cached_output)) => self.preview_output = cached_output,
+                    Ok(None) => {}
+                    Err(e) => {
+                        self.preview_output = format!("Error executing preview command: {}", e);
                     }
                 }
+                return;
             }
 
             // if anything above failed, clear the preview output
+            self.preview.cancel();
             self.preview_output.clear();
         }
     }
 
+    /// Take the output of a finished preview command, returns true if the preview changed
+    pub(crate) fn poll_preview(&mut self) -> bool {
+        match self.preview.poll() {
+            Some(output) => {
+                self.preview_output = output;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Scroll the preview output by a number of lines, negative values scroll up
+    pub fn scroll_preview(&mut self, lines: i32) {
+        let max_offset = self.max_preview_scroll_offset() as i32;
+        self.preview_scroll_offset =
+            (self.preview_scroll_offset as i32 + lines).clamp(0, max_offset) as u16;
+    }
+
+    fn max_preview_scroll_offset(&mut self) -> u16 {
+        let (visible_lines, width) = self.preview_size();
+        if width == 0 {
+            return 0;
+        }
+
+        // wrapping long output is slow, so the lines are only counted again once the output, the
+        // width or the wrapping changed
+        let mut hasher = DefaultHasher::new();
+        self.preview_output.hash(&mut hasher);
+        let output_hash = hasher.finish();
+        let wrap = self.preview_layout.wrap;
+        let rendered_lines = match &self.preview_line_count {
+            Some(count)
+                if count.output_hash == output_hash
+                    && count.width == width
+                    && count.wrap == wrap =>
+            {
+                count.lines
+            }
+            _ => {
+                // count the lines after wrapping so that the end of the output can be reached
+                let lines = preview_paragraph(self).line_count(width);
+                self.preview_line_count = Some(PreviewLineCount {
+                    output_hash,
+                    width,
+                    wrap,
+                    lines,
+                });
+                lines
+            }
+        };
+
+        u16::try_from(rendered_lines)
+            .unwrap_or(u16::MAX)
+            .saturating_sub(visible_lines)
+    }
+
+    /// Whether a preview command is running and the shown output is from a previous item
+    pub fn preview_loading(&self) -> bool {
+        self.preview.is_loading()
+    }
+
     pub(crate) fn first_visible_item_index(&self) -> u32 {
         self.first_visible_item_index
     }
@@ -719,12 +961,12 @@ where
     }
 
     // this should return a valid range that does not exceed the maximum number of items
-    pub(crate) fn visible_item_range(&mut self) -> RangeInclusive<u32> {
+    pub(crate) fn visible_item_range(&self) -> RangeInclusive<u32> {
         // we must use an inclusive range here or we'll be missing items that will cause some weird issues
         self.first_visible_item_index()..=self.last_visible_item_index()
     }
 
-    pub fn matched_items(&mut self) -> Vec<&SelectableItem<T>> {
+    pub fn matched_items(&self) -> Vec<&SelectableItem<T>> {
         // return if the matcher is empty or passing an inclusive range to matched_items will panic
         if self.snapshot().item_count() == 0 {
             return vec![];
@@ -740,7 +982,45 @@ where
             .collect()
     }
 
-    pub(crate) fn selected_items(&self) -> SelectedItems<'_, T> {
+    /// Returns the visible matched items along with the sorted indices of matched characters in each column
+    pub(crate) fn matched_items_with_indices(
+        &mut self,
+    ) -> Vec<(&SelectableItem<T>, Vec<Vec<u32>>)> {
+        // return if the matcher is empty or passing an inclusive range to matched_items will panic
+        if self.snapshot().item_count() == 0 {
+            return vec![];
+        }
+
+        let item_range = self.visible_item_range();
+        let snapshot = self.matcher.snapshot();
+        let matcher = &mut self.highlight_matcher;
+
+        snapshot
+            .matched_items(item_range)
+            .map(|item| {
+                let indices = item
+                    .matcher_columns
+                    .iter()
+                    .enumerate()
+                    .map(|(column, haystack)| {
+                        let mut indices = Vec::new();
+                        snapshot.pattern().column_pattern(column).indices(
+                            haystack.slice(..),
+                            matcher,
+                            &mut indices,
+                        );
+                        indices.sort_unstable();
+                        indices.dedup();
+                        indices
+                    })
+                    .collect();
+                (item.data, indices)
+            })
+            .collect()
+    }
+
+    /// Returns the selected items, or the current item if nothing is selected
+    pub fn selected_items(&self) -> SelectedItems<'_, T> {
         // Get all selected items as references
         let selected_items: Vec<&SelectableItem<T>> = self
             .snapshot()
@@ -749,7 +1029,7 @@ where
             .map(|i| i.data)
             .collect();
 
-        if !selected_items.is_empty() {
+        let selected_items = if !selected_items.is_empty() {
             SelectedItems::from_refs(selected_items)
         } else {
             // If no items are selected, return the current item
@@ -761,7 +1041,8 @@ where
                 .unwrap_or_default();
 
             SelectedItems::from_refs(current_item)
-        }
+        };
+        selected_items.accepted_with(self.query.clone(), self.accepted_key)
     }
 
     /// Returns the index of the last matched item
@@ -770,3 +1051,105 @@ where
         self.snapshot().matched_item_count().saturating_sub(1)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::layout::Rect;
+
+    /// Picker with a preview pane showing `visible_lines` lines of `width` columns
+    fn preview_picker(
+        output: String,
+        width: u16,
+        visible_lines: u16,
+        wrap: bool,
+    ) -> Picker<String> {
+        let mut picker = Picker::<String>::builder()
+            .use_config_file(false)
+            .build()
+            .unwrap();
+        picker.preview_output = output;
+        picker.preview_layout.wrap = wrap;
+        picker.preview_area = Rect::new(0, 0, width + 2, visible_lines + 2);
+        picker
+    }
+
+    fn search_picker() -> Picker<String> {
+        Picker::<String>::builder()
+            .use_config_file(false)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn multibyte_initial_query_can_be_edited() {
+        let mut picker = search_picker();
+        picker.set_query("café");
+        assert_eq!(picker.query_index, 4);
+
+        picker.dispatch(Action::BackwardDeleteChar);
+        assert_eq!(picker.query, "caf");
+        picker.dispatch(Action::Insert('é'));
+        picker.dispatch(Action::BackwardChar);
+        picker.dispatch(Action::Insert('ü'));
+        assert_eq!(picker.query, "cafüé");
+        assert_eq!(picker.query_index, 4);
+    }
+
+    #[test]
+    fn word_actions_move_over_multibyte_characters() {
+        let mut picker = search_picker();
+        picker.set_query("né über");
+        picker.dispatch(Action::BeginningOfLine);
+        picker.dispatch(Action::ForwardWord);
+        assert_eq!(picker.query_index, 3);
+        picker.dispatch(Action::ForwardWord);
+        assert_eq!(picker.query_index, 7);
+        picker.dispatch(Action::ForwardChar);
+        assert_eq!(picker.query_index, 7);
+
+        picker.dispatch(Action::BackwardKillWord);
+        assert_eq!(picker.query, "né ");
+        picker.dispatch(Action::BeginningOfLine);
+        picker.dispatch(Action::ForwardChar);
+        picker.dispatch(Action::KillWord);
+        assert_eq!(picker.query, "n ");
+        picker.dispatch(Action::KillLine);
+        assert_eq!(picker.query, "n");
+    }
+
+    #[test]
+    fn preview_scroll_counts_word_wrapped_lines() {
+        // wrapping at word boundaries takes three lines where the characters would fit in two
+        let mut picker = preview_picker("aaaaaa bbbbbb cccccc".to_string(), 10, 1, true);
+        assert_eq!(picker.max_preview_scroll_offset(), 2);
+
+        let mut picker = preview_picker("aaaaaa bbbbbb cccccc".to_string(), 10, 1, false);
+        assert_eq!(picker.max_preview_scroll_offset(), 0);
+    }
+
+    #[test]
+    fn preview_lines_are_counted_again_after_changes() {
+        let mut picker = preview_picker("aaaaaa bbbbbb cccccc".to_string(), 10, 1, true);
+        assert_eq!(picker.max_preview_scroll_offset(), 2);
+
+        // an unchanged preview uses the count from before
+        if let Some(count) = &mut picker.preview_line_count {
+            count.lines = 50;
+        }
+        assert_eq!(picker.max_preview_scroll_offset(), 49);
+
+        picker.preview_area.width = 18;
+        assert_eq!(picker.max_preview_scroll_offset(), 1);
+        picker.preview_layout.wrap = false;
+        assert_eq!(picker.max_preview_scroll_offset(), 0);
+        picker.preview_output = "a\nb\nc\nd".to_string();
+        assert_eq!(picker.max_preview_scroll_offset(), 3);
+    }
+
+    #[test]
+    fn preview_scroll_saturates_for_long_output() {
+        let mut picker = preview_picker("line\n".repeat(70_000), 10, 5, false);
+        assert_eq!(picker.max_preview_scroll_offset(), u16::MAX - 5);
+    }
+}
@@ -1,3 +1,6 @@
+use crate::picker_item::PickerItem;
+use eunicode::raw_bytes::RawBytes;
+use nucleo::Utf32String;
 use std::{
     fmt::Display,
     sync::atomic::{AtomicBool, Ordering},
@@ -18,6 +21,46 @@ impl<T: Display> Display for SelectableItem<T> {
     }
 }
 
+impl<T: PickerItem> SelectableItem<T> {
+    // Get the text of each column, Requested items only have a single column
+    pub fn columns(&self) -> Vec<String> {
+        match self {
+            SelectableItem::Existing { value, .. } => value.columns(),
+            SelectableItem::Requested { value, .. } => vec![value.clone()],
+        }
+    }
+
+    // Get the text of the whole item
+    pub fn text(&self) -> String {
+        match self {
+            SelectableItem::Existing { value, .. } => value.text(),
+            SelectableItem::Requested { value, .. } => value.clone(),
+        }
+    }
+
+    // Whether the text had to be decoded lossily, Requested items are always valid text
+    pub fn is_lossy(&self) -> bool {
+        match self {
+            SelectableItem::Existing { value, .. } => value.is_lossy(),
+            SelectableItem::Requested { .. } => false,
+        }
+    }
+
+    // Fill the matcher columns, intended to be passed to `Injector::push`
+    pub fn fill_columns(&self, columns: &mut [Utf32String]) {
+        for (column, text) in columns.iter_mut().zip(self.columns()) {
+            // match against the visible text so that color codes don't match and highlights line up
+            *column = if text.contains('\x1b') {
+                RawBytes::from_string(&text)
+                    .strip_ansi_escapes(false)
+                    .into()
+            } else {
+                text.into()
+            };
+        }
+    }
+}
+
 impl<T> SelectableItem<T> {
     // Create a new unselected Existing instance
     pub fn new(value: T) -> Self {
@@ -1,13 +1,41 @@
-use crate::selectable::SelectableItem;
+use crate::{keymap::KeyChord, selectable::SelectableItem};
 
 #[derive(Debug)]
 pub struct SelectedItems<'a, T> {
     items: Vec<&'a SelectableItem<T>>,
+    query: String,
+    accepted_key: Option<KeyChord>,
 }
 
 impl<'a, T> SelectedItems<'a, T> {
     pub fn from_refs(items: Vec<&'a SelectableItem<T>>) -> Self {
-        Self { items }
+        Self {
+            items,
+            query: String::new(),
+            accepted_key: None,
+        }
+    }
+
+    /// Record the query and the key the items were accepted with
+    pub(crate) fn accepted_with(mut self, query: String, accepted_key: Option<KeyChord>) -> Self {
+        self.query = query;
+        self.accepted_key = accepted_key;
+        self
+    }
+
+    /// Returns the query at the time the items were accepted
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// Returns the expected key the items were accepted with, `None` for the regular accept action
+    pub fn accepted_key(&self) -> Option<KeyChord> {
+        self.accepted_key
+    }
+
+    /// Returns the selected items, both existing and requested
+    pub fn items(&self) -> &[&'a SelectableItem<T>] {
+        &self.items
     }
 
     /// Returns a Vec of references to the inner values from Existing selected items
@@ -0,0 +1,164 @@
+use crate::{
+    byte_line::ByteLine,
+    command::{ExecutionMode, kill_process_group, spawn_process_group},
+    placeholders::Placeholders,
+    selectable::SelectableItem,
+};
+use nucleo::Injector;
+use std::{
+    io::{BufRead, BufReader},
+    process::Stdio,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread,
+    time::Duration,
+};
+
+/// How often a running source command is checked for having been cancelled
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Loads the items of a picker, run again whenever the picker is reloaded
+pub type SourceFn<T> = Arc<dyn Fn(&SourceContext, &Injector<SelectableItem<T>>) + Send + Sync>;
+
+/// State of the picker when its source was asked for items
+pub struct SourceContext {
+    /// The query at the time of the reload
+    pub query: String,
+
+    /// Set once a newer reload replaced this one
+    pub(crate) cancelled: Arc<AtomicBool>,
+}
+
+impl SourceContext {
+    /// Whether the items are no longer wanted since the picker was reloaded again
+    ///
+    /// Items pushed after that are ignored, so long running sources should check this and stop.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// Creates a source that runs a command and adds every line of its output as an item
+///
+/// The command template may use `{q}` for the query at the time of the reload, which is the only
+/// placeholder since there is no current item, other braces are passed on unchanged. Lines keep
+/// their original bytes, so output that isn't valid UTF-8 is still listed. With `read0` the output
+/// is split into NUL terminated records instead of lines.
+pub fn command_source(
+    template: String,
+    execution_mode: ExecutionMode,
+    read0: bool,
+) -> impl Fn(&SourceContext, &Injector<SelectableItem<ByteLine>>) + Send + Sync + 'static {
+    move |context, injector| {
+        let placeholders = Placeholders {
+            query: &context.query,
+            query_only: true,
+            ..Default::default()
+        };
+        let Some(mut command) = execution_mode.command(&template, &placeholders) else {
+            return;
+        };
+
+        // output on stderr would be drawn over the picker
+        // TODO might want to show commands that fail to start
+        let Ok(mut child) = spawn_process_group(
+            command
+                .stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null()),
+        ) else {
+            return;
+        };
+
+        let stdout = child.stdout.take();
+        let child = Arc::new(Mutex::new(child));
+        let finished = Arc::new(AtomicBool::new(false));
+
+        // kill the command once a newer reload replaced it, even while it isn't printing anything.
+        // This keeps going after the command itself exited, since processes it started can still
+        // hold its output open
+        let watched_child = child.clone();
+        let watched_finished = finished.clone();
+        let cancelled = context.cancelled.clone();
+        thread::spawn(move || {
+            while !watched_finished.load(Ordering::Relaxed) {
+                if cancelled.load(Ordering::Relaxed) {
+                    // the command may have been reaped in the meantime
+                    if let Ok(mut child) = watched_child.lock()
+                        && !watched_finished.load(Ordering::Relaxed)
+                    {
+                        kill_process_group(&mut child);
+                    }
+                    break;
+                }
+                thread::sleep(CANCEL_POLL_INTERVAL);
+            }
+        });
+
+        if let Some(stdout) = stdout {
+            let terminator = if read0 { b'\0' } else { b'\n' };
+            for mut line in BufReader::new(stdout)
+                .split(terminator)
+                .map_while(Result::ok)
+            {
+                if context.is_cancelled() {
+                    break;
+                }
+                if !read0 && line.last() == Some(&b'\r') {
+                    line.pop();
+                }
+                injector.push(
+                    SelectableItem::new(ByteLine::new(line)),
+                    SelectableItem::fill_columns,
+                );
+            }
+        }
+        finished.store(true, Ordering::Relaxed);
+        if let Ok(mut child) = child.lock() {
+            if context.is_cancelled() {
+                kill_process_group(&mut child);
+            }
+            let _ = child.wait();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::picker::Picker;
+
+    fn source_items(template: &str, read0: bool) -> Vec<String> {
+        let mut picker = Picker::<ByteLine>::builder()
+            .use_config_file(false)
+            .build()
+            .unwrap();
+        picker.set_source(command_source(
+            template.to_string(),
+            ExecutionMode::Shell("sh".to_string()),
+            read0,
+        ));
+        let mut items: Vec<String> = picker
+            .filter("", None)
+            .items()
+            .iter()
+            .map(|item| item.text())
+            .collect();
+        items.sort();
+        items
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn output_is_split_into_lines() {
+        assert_eq!(source_items(r"printf 'a\r\nb c\n'", false), ["a", "b c"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn read0_splits_output_on_nul() {
+        assert_eq!(source_items(r"printf 'a\nb\0c\0'", true), ["a\nb", "c"]);
+    }
+}
@@ -1,104 +1,95 @@
-use crate::picker::Picker;
+use crate::{
+    action::Action,
+    keymap::Keymap,
+    picker::{Picker, PickerMode},
+    picker_item::PickerItem,
+};
 use ansi_to_tui::IntoText;
 use ratatui::{
+    Frame,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span, Text},
     widgets::{Block, Borders, List, ListItem, Paragraph},
-    Frame,
 };
-use std::fmt::Display;
+
+/// Separator used between the visible columns of an item
+const COLUMN_SEPARATOR: &str = "  ";
 
 pub fn ui<T>(f: &mut Frame, app: &mut Picker<T>)
 where
-    T: Sync + Send + Display,
+    T: Sync + Send + PickerItem,
 {
+    let area = picker_area(f.area(), app.config.height());
+
     match app.mode {
         crate::picker::PickerMode::Help => {
-            render_help_screen(f, f.area(), app.help_scroll_offset);
+            render_help_screen(f, area, app);
         }
         _ => {
-            if app.has_preview() {
-                // Split screen horizontally for preview mode
-                let main_chunks = Layout::default()
-                    .direction(Direction::Horizontal)
-                    .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
-                    .split(f.area());
-
-                // Left side - normal picker interface
-                let left_chunks = Layout::default()
-                    .direction(Direction::Vertical)
-                    .constraints(
-                        [
-                            Constraint::Length(1),
-                            Constraint::Length(3),
-                            Constraint::Min(1),
-                        ]
-                        .as_ref(),
-                    )
-                    .split(main_chunks[0]);
-
-                // update the height before rendering so this doesn't get out of sync
-                app.update_height(left_chunks[2].height - 3);
-
-                // render the sections of the display now that everything is setup and updated
-                render_help(f, left_chunks[0], app);
-                render_search_input(f, app, left_chunks[1]);
-                render_items(f, app, left_chunks[2]);
-
-                // Right side - preview
-                render_preview(f, app, main_chunks[1]);
+            let (list_area, preview_area) = if app.has_preview() {
+                app.preview_layout.split(area)
             } else {
-                // Normal full-screen mode
-                let chunks = Layout::default()
-                    .direction(Direction::Vertical)
-                    .constraints(
-                        [
-                            Constraint::Length(1),
-                            Constraint::Length(3),
-                            Constraint::Min(1),
-                        ]
-                        .as_ref(),
-                    )
-                    .split(f.area());
-
-                // update the height before rendering so this doesn't get out of sync
-                // TODO ensure that 3 is always correct or pull the correct value that takes terminal resizing into account
-                app.update_height(chunks[2].height - 3);
-
-                // render the sections of the display now that everything is setup and updated
-                render_help(f, chunks[0], app);
-                render_search_input(f, app, chunks[1]);
-                render_items(f, app, chunks[2]);
+                (area, None)
+            };
+
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(
+                    [
+                        Constraint::Length(1),
+                        Constraint::Length(3),
+                        Constraint::Min(1),
+                    ]
+                    .as_ref(),
+                )
+                .split(list_area);
+
+            // update the height before rendering so this doesn't get out of sync
+            // TODO ensure that 3 is always correct or pull the correct value that takes terminal resizing into account
+            app.update_height(chunks[2].height.saturating_sub(3));
+
+            // render the sections of the display now that everything is setup and updated
+            render_help(f, chunks[0], app);
+            render_search_input(f, app, chunks[1]);
+            render_items(f, app, chunks[2]);
+
+            match preview_area {
+                Some(preview_area) => render_preview(f, app, preview_area),
+                // an empty area tells the picker that the preview isn't shown
+                None => app.preview_area = Rect::default(),
             }
         }
     }
 }
 
+/// Restrict the drawing area to the configured height, anchored to the top of the screen
+fn picker_area(area: Rect, height: Option<u16>) -> Rect {
+    match height {
+        Some(height) => Rect {
+            height: height.min(area.height),
+            ..area
+        },
+        None => area,
+    }
+}
+
 fn render_help<T>(f: &mut Frame, area: Rect, app: &Picker<T>)
 where
-    T: Sync + Send + Display,
+    T: Sync + Send + PickerItem,
 {
-    let left_text = vec![Line::from(vec![
-        Span::raw("Press "),
-        Span::styled("↑/↓", Style::default().add_modifier(Modifier::BOLD)),
-        Span::raw(" to navigate, "),
-        Span::styled("Tab", Style::default().add_modifier(Modifier::BOLD)),
-        Span::raw(" to select, "),
-        Span::styled("Enter", Style::default().add_modifier(Modifier::BOLD)),
-        Span::raw(" to confirm, "),
-        Span::styled("Esc", Style::default().add_modifier(Modifier::BOLD)),
-        Span::raw(" to quit"),
-    ])];
-
-    let right_text = vec![Line::from(vec![
-        Span::styled(
-            app.running_threads().to_string(),
-            Style::default().add_modifier(Modifier::BOLD),
-        ),
-        Span::raw(" indexers"),
-    ])
-    .right_aligned()];
+    let left_text = vec![key_hints(&app.keymap)];
+
+    let right_text = vec![
+        Line::from(vec![
+            Span::styled(
+                app.running_threads().to_string(),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(" indexers"),
+        ])
+        .right_aligned(),
+    ];
 
     let spans = Layout::default()
         .direction(Direction::Horizontal)
@@ -113,9 +104,12 @@ where
 
 fn render_search_input<T>(f: &mut Frame, app: &Picker<T>, area: Rect)
 where
-    T: Sync + Send + Display,
+    T: Sync + Send + PickerItem,
 {
     let (text, cursor_index, title) = match app.mode {
+        crate::picker::PickerMode::Search if app.interactive => {
+            (&app.query, app.query_index, "Command")
+        }
         crate::picker::PickerMode::Search => (&app.query, app.query_index, "Search"),
         crate::picker::PickerMode::Editing => (&app.editing_text, app.editing_index, "Editing"),
         crate::picker::PickerMode::Help => return,
@@ -138,15 +132,24 @@ where
         .block(Block::default().borders(Borders::ALL).title(title));
 
     let snapshot = app.snapshot();
-    let item_count_text = vec![Line::from(vec![Span::styled(
+    let mut status_spans = Vec::new();
+    if app.lossy_item_count > 0 {
+        // items that weren't valid UTF-8 are shown with replacement characters
+        status_spans.push(Span::styled(
+            format!("{} lossy  ", app.lossy_item_count),
+            Style::default().fg(Color::Yellow),
+        ));
+    }
+    status_spans.push(Span::styled(
         format!(
             "{}/{}",
             snapshot.matched_item_count(),
             snapshot.item_count()
         ),
         Style::default().add_modifier(Modifier::BOLD),
-    )])
-    .right_aligned()];
+    ));
+    let status_width = status_spans.iter().map(Span::width).sum::<usize>() as u16;
+    let item_count_text = vec![Line::from(status_spans).right_aligned()];
 
     let item_count: Paragraph<'_> = Paragraph::new(item_count_text)
         .style(Style::default())
@@ -154,7 +157,11 @@ where
 
     let spans = Layout::default()
         .direction(Direction::Horizontal)
-        .constraints([Constraint::Min(40), Constraint::Length(17)])
+        .constraints([
+            Constraint::Min(40),
+            // room for the borders and some padding
+            Constraint::Length((status_width + 4).max(17)),
+        ])
         .split(area);
 
     f.render_widget(input, spans[0]);
@@ -163,18 +170,24 @@ where
 
 fn render_items<T>(f: &mut Frame, app: &mut Picker<T>, area: Rect)
 where
-    T: Sync + Send + Display,
+    T: Sync + Send + PickerItem,
 {
     match app.mode {
         crate::picker::PickerMode::Editing => {
             render_autocomplete_suggestions(f, app, area);
         }
         crate::picker::PickerMode::Search => {
+            // remember where the items are so clicks can be mapped to them
+            app.items_area = area;
+
             if app.matched_item_count() > 0 {
+                // copied up front since the matched items keep the picker borrowed
+                let visible_columns: Vec<bool> = app.columns.iter().map(|c| c.visible).collect();
+
                 let items: Vec<ListItem> = app
-                    .matched_items()
-                    .iter()
-                    .map(|item| {
+                    .matched_items_with_indices()
+                    .into_iter()
+                    .map(|(item, indices)| {
                         let is_selected = item.is_selected();
                         let style = if is_selected {
                             Style::default()
@@ -185,19 +198,25 @@ where
                         };
 
                         let prefix = if is_selected { "✓ " } else { "  " };
-                        let item_str = format!("{}", item);
-
-                        // Parse ANSI codes in the item display for colored filenames
-                        let item_text = match item_str.as_str().into_text() {
-                            Ok(text) => text,
-                            Err(_) => Text::raw(item_str.clone()),
+                        let mut lines = if item.is_requested() {
+                            highlight_matches(&item.text(), &indices[0])
+                        } else {
+                            // only show the columns that are marked as visible
+                            let column_texts = item
+                                .columns()
+                                .iter()
+                                .zip(&indices)
+                                .zip(&visible_columns)
+                                .filter(|(_, visible)| **visible)
+                                .map(|((text, indices), _)| highlight_matches(text, indices))
+                                .collect();
+                            join_columns(column_texts)
                         };
 
                         // Prepend the prefix to the first line
-                        let mut lines: Vec<Line> = item_text.lines.into_iter().collect();
                         if let Some(first_line) = lines.first_mut() {
                             let mut new_spans = vec![Span::raw(prefix)];
-                            new_spans.extend(first_line.spans.drain(..));
+                            new_spans.append(&mut first_line.spans);
                             first_line.spans = new_spans;
                         } else {
                             lines.push(Line::from(prefix));
@@ -245,13 +264,95 @@ where
                 f.render_widget(no_items_paragraph, chunks[1]);
             }
         }
-        crate::picker::PickerMode::Help => return,
+        crate::picker::PickerMode::Help => {}
+    }
+}
+
+/// Parse ANSI codes in the item text and highlight the characters at the matched indices
+fn highlight_matches(text: &str, indices: &[u32]) -> Vec<Line<'static>> {
+    // Parse ANSI codes in the item display for colored filenames
+    let item_text = match text.into_text() {
+        Ok(text) => text,
+        Err(_) => Text::raw(text.to_string()),
+    };
+
+    if indices.is_empty() {
+        return item_text.lines;
+    }
+
+    // NOTE: indices count the characters of the text with ANSI codes removed, which is what the parsed spans contain
+    // nucleo counts grapheme clusters rather than chars, so text with combining characters may be slightly off
+    let mut char_index: u32 = 0;
+    item_text
+        .lines
+        .into_iter()
+        .map(|mut line| {
+            let mut spans = Vec::new();
+            for span in std::mem::take(&mut line.spans) {
+                // split each span into runs of highlighted and plain characters
+                let mut run = String::new();
+                let mut run_highlighted = false;
+                for c in span.content.chars() {
+                    let highlighted = indices.binary_search(&char_index).is_ok();
+                    if highlighted != run_highlighted && !run.is_empty() {
+                        spans.push(match_span(
+                            std::mem::take(&mut run),
+                            span.style,
+                            run_highlighted,
+                        ));
+                    }
+                    run_highlighted = highlighted;
+                    run.push(c);
+                    char_index += 1;
+                }
+                if !run.is_empty() {
+                    spans.push(match_span(run, span.style, run_highlighted));
+                }
+            }
+            // account for the line break between lines
+            char_index += 1;
+            line.spans = spans;
+            line
+        })
+        .collect()
+}
+
+fn match_span(content: String, style: Style, highlighted: bool) -> Span<'static> {
+    if highlighted {
+        Span::styled(
+            content,
+            style.patch(
+                Style::default()
+                    .fg(Color::LightGreen)
+                    .add_modifier(Modifier::BOLD),
+            ),
+        )
+    } else {
+        Span::styled(content, style)
     }
 }
 
+/// Join the lines of each column, placing the columns next to each other
+fn join_columns(column_texts: Vec<Vec<Line<'static>>>) -> Vec<Line<'static>> {
+    let mut lines: Vec<Line<'static>> = Vec::new();
+    for column_lines in column_texts {
+        let mut column_lines = column_lines.into_iter();
+        match (lines.last_mut(), column_lines.next()) {
+            (Some(last_line), Some(first_line)) => {
+                last_line.spans.push(Span::raw(COLUMN_SEPARATOR));
+                last_line.spans.extend(first_line.spans);
+            }
+            (None, Some(first_line)) => lines.push(first_line),
+            (_, None) => {}
+        }
+        lines.extend(column_lines);
+    }
+    lines
+}
+
 fn render_autocomplete_suggestions<T>(f: &mut Frame, app: &Picker<T>, area: Rect)
 where
-    T: Sync + Send + Display,
+    T: Sync + Send + PickerItem,
 {
     if !app.autocomplete_suggestions.is_empty() {
         let items: Vec<ListItem> = app
@@ -307,9 +408,31 @@ where
     }
 }
 
-fn render_preview<T>(f: &mut Frame, app: &Picker<T>, area: Rect)
+fn render_preview<T>(f: &mut Frame, app: &mut Picker<T>, area: Rect)
 where
-    T: Sync + Send + Display,
+    T: Sync + Send + PickerItem,
+{
+    // remember the size so preview commands can fit their output to it
+    app.preview_area = area;
+
+    // keep showing the previous output while the next preview is loading
+    let title = if app.preview_loading() {
+        "Preview (loading…)"
+    } else {
+        "Preview"
+    };
+
+    let preview = preview_paragraph(app)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .scroll((app.preview_scroll_offset, 0));
+
+    f.render_widget(preview, area);
+}
+
+/// The preview output as it is drawn, also used to find how far the preview can be scrolled
+pub(crate) fn preview_paragraph<T>(app: &Picker<T>) -> Paragraph<'static>
+where
+    T: Sync + Send + PickerItem,
 {
     // using the `ansi-to-tui` crate to allow correct rendering of colored text
     let lines = match app.preview_output().into_text() {
@@ -317,15 +440,84 @@ where
         Err(e) => Text::from(format!("Could not render preview: {e}")),
     };
 
-    let preview = Paragraph::new(lines)
-        .block(Block::default().borders(Borders::ALL).title("Preview"))
-        .wrap(ratatui::widgets::Wrap { trim: false });
+    let preview = Paragraph::new(lines);
+    if app.preview_layout.wrap {
+        preview.wrap(ratatui::widgets::Wrap { trim: false })
+    } else {
+        preview
+    }
+}
 
-    f.render_widget(preview, area);
+fn render_help_screen<T>(f: &mut Frame, area: Rect, app: &Picker<T>)
+where
+    T: Sync + Send + PickerItem,
+{
+    let help_paragraph = Paragraph::new(help_lines(&app.keymap))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(help_title(&app.keymap)),
+        )
+        .alignment(Alignment::Left)
+        .wrap(ratatui::widgets::Wrap { trim: false })
+        .scroll((app.help_scroll_offset, 0));
+
+    f.render_widget(help_paragraph, area);
 }
 
-fn render_help_screen(f: &mut Frame, area: Rect, scroll_offset: u16) {
-    let help_text = vec![
+/// The keys for the most common search actions, e.g. "Press ↑/↓ to navigate, Tab to select"
+fn key_hints(keymap: &Keymap) -> Line<'static> {
+    let mode = PickerMode::Search;
+    let first_chord = |actions: &[Action]| {
+        actions
+            .iter()
+            .find_map(|action| action_chords(keymap, mode, action).into_iter().next())
+    };
+    let navigate = first_chord(&[Action::Up])
+        .zip(first_chord(&[Action::Down]))
+        .map(|(up, down)| format!("{up}/{down}"));
+    let hints = [
+        (navigate, "navigate"),
+        (first_chord(&[Action::ToggleDown, Action::Toggle]), "select"),
+        (first_chord(&[Action::Accept]), "confirm"),
+        (first_chord(&[Action::Cancel, Action::Abort]), "quit"),
+    ];
+
+    // actions without a key are left out
+    let mut spans = Vec::new();
+    for (keys, description) in hints {
+        let Some(keys) = keys else {
+            continue;
+        };
+        spans.push(Span::raw(if spans.is_empty() { "Press " } else { ", " }));
+        spans.push(Span::styled(
+            keys,
+            Style::default().add_modifier(Modifier::BOLD),
+        ));
+        spans.push(Span::raw(format!(" to {description}")));
+    }
+    Line::from(spans)
+}
+
+/// Title of the help screen naming the keys that scroll it, e.g. "Help - Use k/j or ↑/↓ to scroll"
+fn help_title(keymap: &Keymap) -> String {
+    let up = action_chords(keymap, PickerMode::Help, &Action::Up);
+    let down = action_chords(keymap, PickerMode::Help, &Action::Down);
+    let pairs: Vec<String> = up
+        .iter()
+        .zip(&down)
+        .map(|(up, down)| format!("{up}/{down}"))
+        .collect();
+    if pairs.is_empty() {
+        "Help".to_string()
+    } else {
+        format!("Help - Use {} to scroll", pairs.join(" or "))
+    }
+}
+
+/// The contents of the help screen
+pub(crate) fn help_lines(keymap: &Keymap) -> Vec<Line<'static>> {
+    let mut lines = vec![
         Line::from(vec![Span::styled(
             "Picleo Help",
             Style::default()
@@ -333,77 +525,196 @@ fn render_help_screen(f: &mut Frame, area: Rect, scroll_offset: u16) {
                 .fg(Color::Yellow),
         )]),
         Line::from(""),
-        Line::from(vec![Span::styled(
-            "Search Mode:",
-            Style::default()
-                .add_modifier(Modifier::BOLD)
-                .fg(Color::Green),
-        )]),
-        Line::from("  ↑/↓, PgUp/PgDn, Home/End  Navigate items"),
-        Line::from("  Tab                       Toggle item selection"),
-        Line::from("  Enter                     Confirm selection and exit"),
-        Line::from("  Esc                       Clear query or exit"),
-        Line::from("  Ctrl+c                    Exit program"),
-        Line::from("  Ctrl+u                    Clear query"),
-        Line::from("  Ctrl+a/e                  Move to start/end of query"),
-        Line::from("  Ctrl+k                    Delete to end of query"),
-        Line::from("  Ctrl+Backspace/Alt+Backspace  Delete word backward"),
-        Line::from("  Ctrl+Delete/Alt+Delete    Delete word forward"),
-        Line::from("  Ctrl+Left/Right           Jump word backward/forward"),
-        Line::from("  Ctrl+d                    Edit current item (if editable)"),
-        Line::from("  Ctrl+n                    Create new item (if editable)"),
-        Line::from("  Ctrl+h                    Show this help"),
-        Line::from(""),
-        Line::from(vec![Span::styled(
-            "Editing Mode:",
-            Style::default()
-                .add_modifier(Modifier::BOLD)
-                .fg(Color::Green),
-        )]),
-        Line::from("  ↑/↓                       Navigate autocomplete suggestions"),
-        Line::from("  Tab                       Toggle autocomplete selection"),
-        Line::from("  Enter                     Create items and return to search"),
-        Line::from("  Esc                       Cancel editing and return to search"),
-        Line::from("  Ctrl+c                    Exit program"),
-        Line::from("  Ctrl+u                    Clear editing text"),
-        Line::from("  Ctrl+a/e                  Move to start/end of text"),
-        Line::from("  Ctrl+k                    Delete to end of line"),
-        Line::from("  Ctrl+Backspace/Shift+Backspace  Delete word backward"),
-        Line::from("  Ctrl+Delete/Shift+Delete  Delete word forward"),
-        Line::from("  Ctrl+Left/Right           Jump word backward/forward"),
-        Line::from("  Ctrl+h                    Show this help"),
-        Line::from(""),
-        Line::from(vec![Span::styled(
-            "Mouse Support:",
-            Style::default()
-                .add_modifier(Modifier::BOLD)
-                .fg(Color::Green),
-        )]),
+    ];
+
+    // list the actual bindings so custom keymaps are described correctly
+    for (mode, title) in [
+        (PickerMode::Search, "Search Mode:"),
+        (PickerMode::Editing, "Editing Mode:"),
+        (PickerMode::Help, "Help Screen:"),
+    ] {
+        lines.push(section_title(title));
+        for (keys, action) in bound_keys(keymap, mode) {
+            lines.push(Line::from(format!(
+                "  {keys:<24}  {}",
+                action_description(mode, &action)
+            )));
+        }
+        lines.push(Line::from(""));
+    }
+
+    lines.extend([
+        section_title("Mouse Support:"),
         Line::from("  Scroll                    Navigate items"),
         Line::from("  Shift/Ctrl+Scroll         Page navigation"),
         Line::from("  Left Click                Toggle item selection"),
         Line::from("  Middle Click              Toggle current item selection"),
-        Line::from(""),
-        Line::from(vec![
-            Span::styled("Press ", Style::default()),
-            Span::styled("Esc", Style::default().add_modifier(Modifier::BOLD)),
-            Span::styled(", ", Style::default()),
-            Span::styled("q", Style::default().add_modifier(Modifier::BOLD)),
-            Span::styled(", or ", Style::default()),
-            Span::styled("Ctrl+h", Style::default().add_modifier(Modifier::BOLD)),
-            Span::styled(" to close this help", Style::default()),
-        ]),
-    ];
+        Line::from("  Scroll over preview       Scroll preview"),
+    ]);
 
-    let help_paragraph = Paragraph::new(help_text)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title("Help - Use ↑/↓ or j/k to scroll"),
-        )
-        .alignment(Alignment::Left)
-        .wrap(ratatui::widgets::Wrap { trim: false })
-        .scroll((scroll_offset, 0));
+    lines
+}
 
-    f.render_widget(help_paragraph, area);
+fn section_title(title: &'static str) -> Line<'static> {
+    Line::from(vec![Span::styled(
+        title,
+        Style::default()
+            .add_modifier(Modifier::BOLD)
+            .fg(Color::Green),
+    )])
+}
+
+/// Group the key chords of a mode by the action they are bound to, in help screen order
+fn bound_keys(keymap: &Keymap, mode: PickerMode) -> Vec<(String, Action)> {
+    let mut actions: Vec<(Action, Vec<String>)> = Vec::new();
+    for (chord, action) in keymap.bindings(mode) {
+        if *action == Action::Ignore {
+            continue;
+        }
+        match actions.iter_mut().find(|(bound, _)| bound == action) {
+            Some((_, chords)) => chords.push(chord.to_string()),
+            None => actions.push((action.clone(), vec![chord.to_string()])),
+        }
+    }
+
+    actions.sort_by_key(|(action, _)| action.help_order());
+    actions
+        .into_iter()
+        .map(|(action, mut chords)| {
+            sort_chords(&mut chords);
+            (chords.join(", "), action)
+        })
+        .collect()
+}
+
+/// The key chords bound to an action in a mode, in the order the help screen lists them
+fn action_chords(keymap: &Keymap, mode: PickerMode, action: &Action) -> Vec<String> {
+    let mut chords: Vec<String> = keymap
+        .bindings(mode)
+        .iter()
+        .filter(|(_, bound)| *bound == action)
+        .map(|(chord, _)| chord.to_string())
+        .collect();
+    sort_chords(&mut chords);
+    chords
+}
+
+fn sort_chords(chords: &mut [String]) {
+    // sort by length first so plain keys are listed before their modified versions
+    chords.sort_by_key(|chord| (chord.chars().count(), chord.clone()));
+}
+
+fn action_description(mode: PickerMode, action: &Action) -> String {
+    let description = match (mode, action) {
+        (PickerMode::Search, Action::Up) => "Previous item",
+        (PickerMode::Search, Action::Down) => "Next item",
+        (PickerMode::Search, Action::PageUp) => "Previous page of items",
+        (PickerMode::Search, Action::PageDown) => "Next page of items",
+        (PickerMode::Search, Action::First) => "First item",
+        (PickerMode::Search, Action::Last) => "Last item",
+        (PickerMode::Search, Action::Accept) => "Confirm selection and exit",
+        (PickerMode::Search, Action::Cancel) => "Clear query or exit",
+        (PickerMode::Search, Action::ClearQuery) => "Clear query",
+        (PickerMode::Search, Action::BeginningOfLine) => "Move to start of query",
+        (PickerMode::Search, Action::EndOfLine) => "Move to end of query",
+        (PickerMode::Search, Action::KillLine) => "Delete to end of query",
+        (PickerMode::Search, Action::Help) => "Show this help",
+        (PickerMode::Editing, Action::Up) => "Previous autocomplete suggestion",
+        (PickerMode::Editing, Action::Down) => "Next autocomplete suggestion",
+        (PickerMode::Editing, Action::Toggle) => "Toggle autocomplete selection",
+        (PickerMode::Editing, Action::ToggleDown) => "Toggle autocomplete selection",
+        (PickerMode::Editing, Action::Accept) => "Create items and return to search",
+        (PickerMode::Editing, Action::Cancel) => "Cancel editing and return to search",
+        (PickerMode::Editing, Action::ClearQuery) => "Clear editing text",
+        (PickerMode::Editing, Action::BeginningOfLine) => "Move to start of text",
+        (PickerMode::Editing, Action::EndOfLine) => "Move to end of text",
+        (PickerMode::Editing, Action::KillLine) => "Delete to end of line",
+        (PickerMode::Editing, Action::Help) => "Show this help",
+        (PickerMode::Help, Action::Up) => "Scroll up",
+        (PickerMode::Help, Action::Down) => "Scroll down",
+        (PickerMode::Help, Action::PageUp) => "Scroll up by a page",
+        (PickerMode::Help, Action::PageDown) => "Scroll down by a page",
+        (PickerMode::Help, Action::First) => "Scroll to the top",
+        (PickerMode::Help, Action::Last) => "Scroll to the bottom",
+        (PickerMode::Help, Action::Cancel | Action::Help) => "Close this help",
+        (_, Action::Toggle) => "Toggle item selection",
+        (_, Action::ToggleDown) => "Toggle item selection and move down",
+        (_, Action::Abort) => "Exit program",
+        (_, Action::BackwardChar) => "Move back one character",
+        (_, Action::ForwardChar) => "Move forward one character",
+        (_, Action::BackwardWord) => "Jump word backward",
+        (_, Action::ForwardWord) => "Jump word forward",
+        (_, Action::BackwardDeleteChar) => "Delete character backward",
+        (_, Action::DeleteChar) => "Delete character forward",
+        (_, Action::BackwardKillWord) => "Delete word backward",
+        (_, Action::KillWord) => "Delete word forward",
+        (_, Action::EditItem) => "Edit current item (if editable)",
+        (_, Action::NewItem) => "Create new item (if editable)",
+        (_, Action::Reload) => "Reload items",
+        (_, Action::ToggleInteractive) => "Toggle query driving the source",
+        (_, Action::TogglePreview) => "Toggle preview",
+        (_, Action::CyclePreview) => "Cycle preview position",
+        (_, Action::PreviewUp) => "Scroll preview up by line",
+        (_, Action::PreviewDown) => "Scroll preview down by line",
+        (_, Action::PreviewPageUp) => "Scroll preview up by page",
+        (_, Action::PreviewPageDown) => "Scroll preview down by page",
+        (_, action) => return action.to_string(),
+    };
+    description.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keymap::KeyChord;
+
+    fn line_text(line: &Line) -> String {
+        line.spans
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect()
+    }
+
+    fn chord(name: &str) -> KeyChord {
+        name.parse().unwrap()
+    }
+
+    #[test]
+    fn key_hints_show_the_default_keys() {
+        assert_eq!(
+            line_text(&key_hints(&Keymap::default())),
+            "Press ↑/↓ to navigate, Tab to select, Enter to confirm, Esc to quit"
+        );
+    }
+
+    #[test]
+    fn key_hints_follow_rebound_keys() {
+        let mut keymap = Keymap::default();
+        let search = PickerMode::Search;
+        keymap.bind(search, chord("up"), Action::Ignore);
+        keymap.bind(search, chord("ctrl-p"), Action::Up);
+        keymap.bind(search, chord("tab"), Action::Ignore);
+        keymap.bind(search, chord("ctrl-a"), Action::Accept);
+        keymap.bind(search, chord("enter"), Action::Ignore);
+        assert_eq!(
+            line_text(&key_hints(&keymap)),
+            "Press Ctrl+p/↓ to navigate, Ctrl+a to confirm, Esc to quit"
+        );
+
+        let mut keymap = Keymap::empty();
+        keymap.bind(search, chord("ctrl-c"), Action::Abort);
+        assert_eq!(line_text(&key_hints(&keymap)), "Press Ctrl+c to quit");
+    }
+
+    #[test]
+    fn help_title_names_the_scroll_keys() {
+        let mut keymap = Keymap::default();
+        assert_eq!(help_title(&keymap), "Help - Use k/j or ↑/↓ to scroll");
+
+        keymap.bind(PickerMode::Help, chord("k"), Action::Ignore);
+        keymap.bind(PickerMode::Help, chord("j"), Action::Ignore);
+        assert_eq!(help_title(&keymap), "Help - Use ↑/↓ to scroll");
+
+        assert_eq!(help_title(&Keymap::empty()), "Help");
+    }
 }